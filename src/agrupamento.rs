@@ -0,0 +1,265 @@
+// Agrupamento das notas por um critério (prestador, competência etc.) com subtotais.
+
+use crate::formato::somar_valores;
+use crate::ParsedInvoice;
+
+/// Conjunto de notas que compartilham a mesma chave de agrupamento.
+pub struct Grupo {
+    /// Valor que identifica o grupo (por exemplo, o CNPJ do prestador).
+    pub chave: String,
+    /// Nome exibido para o grupo.
+    pub nome: String,
+    /// Índices das notas do grupo em `parsed_invoices`.
+    pub notas: Vec<usize>,
+    /// Soma de `valor_servicos` das notas do grupo.
+    pub total: f64,
+    /// Soma do ISS das notas do grupo que o informam.
+    pub total_iss: f64,
+}
+
+/// Agrupa as notas indicadas por `indices` usando a função `chave_e_nome`, que devolve a chave e o nome do grupo.
+/// Os grupos aparecem na ordem em que a primeira nota de cada um é encontrada.
+pub fn agrupar<F>(invoices: &[ParsedInvoice], indices: &[usize], chave_e_nome: F) -> Vec<Grupo>
+where
+    F: Fn(&ParsedInvoice) -> (String, String),
+{
+    let mut grupos: Vec<Grupo> = Vec::new();
+    let mut posicoes = std::collections::HashMap::new();
+    for &i in indices {
+        let parsed = &invoices[i];
+        let (chave, nome) = chave_e_nome(parsed);
+        let posicao = *posicoes.entry(chave.clone()).or_insert_with(|| {
+            grupos.push(Grupo { chave, nome, notas: Vec::new(), total: 0.0, total_iss: 0.0 });
+            grupos.len() - 1
+        });
+        grupos[posicao].notas.push(i);
+    }
+    for grupo in &mut grupos {
+        let valores = grupo.notas.iter().map(|&i| &invoices[i].inf.servico.valores);
+        grupo.total = somar_valores(valores.clone().map(|v| v.valor_servicos));
+        grupo.total_iss = somar_valores(valores.filter_map(|v| v.valor_iss));
+    }
+    grupos
+}
+
+/// Agrupa as notas pelo CNPJ do prestador, do maior para o menor total faturado.
+pub fn por_prestador(invoices: &[ParsedInvoice], indices: &[usize]) -> Vec<Grupo> {
+    let mut grupos = agrupar(invoices, indices, |parsed| {
+        let prestador = &parsed.inf.prestador_servico;
+        (prestador.identificacao_prestador.cnpj.clone(), prestador.razao_social.clone())
+    });
+    grupos.sort_by(|a, b| b.total.total_cmp(&a.total).then_with(|| a.nome.cmp(&b.nome)));
+    grupos
+}
+
+/// Chave usada no agrupamento por tomador para as notas sem CPF/CNPJ do tomador.
+pub const SEM_DOCUMENTO: &str = "sem documento";
+
+/// Agrupa as notas pelo CPF ou CNPJ do tomador (ou pelo NIF, no exterior).
+pub fn por_tomador(invoices: &[ParsedInvoice], indices: &[usize]) -> Vec<Grupo> {
+    agrupar(invoices, indices, |parsed| {
+        let tomador = &parsed.inf.tomador_servico;
+        let documento = tomador.identificacao_tomador.documento().unwrap_or(SEM_DOCUMENTO);
+        (documento.to_string(), tomador.nome().unwrap_or("").to_string())
+    })
+}
+
+/// Chave usada para as notas sem item da lista de serviços no agrupamento por item.
+pub const SEM_ITEM: &str = "sem item";
+
+/// Grupos de itens da lista de serviços da LC 116/2003, na ordem do código (`01` a `40`).
+const GRUPOS_LC116: [&str; 40] = [
+    "Serviços de informática e congêneres",
+    "Serviços de pesquisas e desenvolvimento",
+    "Serviços de locação, cessão de direito de uso e congêneres",
+    "Serviços de saúde, assistência médica e congêneres",
+    "Serviços de medicina e assistência veterinária e congêneres",
+    "Serviços de cuidados pessoais, estética, atividades físicas e congêneres",
+    "Serviços de engenharia, arquitetura, construção civil, manutenção, limpeza e congêneres",
+    "Serviços de educação, ensino, treinamento e avaliação pessoal",
+    "Serviços de hospedagem, turismo, viagens e congêneres",
+    "Serviços de intermediação e congêneres",
+    "Serviços de guarda, estacionamento, armazenamento, vigilância e congêneres",
+    "Serviços de diversões, lazer, entretenimento e congêneres",
+    "Serviços de fonografia, fotografia, cinematografia e reprografia",
+    "Serviços relativos a bens de terceiros",
+    "Serviços do setor bancário ou financeiro",
+    "Serviços de transporte de natureza municipal",
+    "Serviços de apoio técnico, administrativo, jurídico, contábil, comercial e congêneres",
+    "Serviços de regulação de sinistros e gerência de riscos seguráveis",
+    "Serviços de distribuição e venda de bilhetes de loteria, bingos e apostas",
+    "Serviços portuários, aeroportuários, ferroportuários e de terminais",
+    "Serviços de registros públicos, cartorários e notariais",
+    "Serviços de exploração de rodovia",
+    "Serviços de programação e comunicação visual, desenho industrial e congêneres",
+    "Serviços de chaveiros, carimbos, placas, sinalização visual e congêneres",
+    "Serviços funerários",
+    "Serviços de coleta, remessa ou entrega de correspondências e objetos",
+    "Serviços de assistência social",
+    "Serviços de avaliação de bens e serviços",
+    "Serviços de biblioteconomia",
+    "Serviços de biologia, biotecnologia e química",
+    "Serviços técnicos em edificações, eletrônica, mecânica, telecomunicações e congêneres",
+    "Serviços de desenhos técnicos",
+    "Serviços de desembaraço aduaneiro, despachantes e congêneres",
+    "Serviços de investigações particulares, detetives e congêneres",
+    "Serviços de reportagem, assessoria de imprensa, jornalismo e relações públicas",
+    "Serviços de meteorologia",
+    "Serviços de artistas, atletas, modelos e manequins",
+    "Serviços de museologia",
+    "Serviços de ourivesaria e lapidação",
+    "Serviços relativos a obras de arte sob encomenda",
+];
+
+/// Descrição do grupo da LC 116/2003 a que pertence o item da lista de serviços, aceito como `0107`, `107`
+/// ou `1.07`; `None` quando o código não corresponde a nenhum grupo.
+pub fn descricao_item_servico(item: &str) -> Option<&'static str> {
+    let item = item.trim();
+    let grupo = match item.split_once('.') {
+        Some((grupo, _)) => grupo,
+        None => item.get(..item.len().checked_sub(2)?)?,
+    };
+    let numero: usize = grupo.parse().ok()?;
+    GRUPOS_LC116.get(numero.checked_sub(1)?).copied()
+}
+
+/// Agrupa as notas pelo item da lista de serviços, em ordem de código, com a descrição do grupo da LC 116/2003
+/// como nome quando o código é reconhecido (senão, o próprio código).
+/// As notas que não informam o item ficam num grupo "sem item" ao final.
+pub fn por_item_servico(invoices: &[ParsedInvoice], indices: &[usize]) -> Vec<Grupo> {
+    let mut grupos = agrupar(invoices, indices, |parsed| {
+        let item = parsed
+            .inf
+            .servico
+            .item_lista_servico
+            .as_deref()
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .unwrap_or(SEM_ITEM);
+        (item.to_string(), descricao_item_servico(item).unwrap_or(item).to_string())
+    });
+    grupos.sort_by(|a, b| (a.chave == SEM_ITEM, &a.chave).cmp(&(b.chave == SEM_ITEM, &b.chave)));
+    grupos
+}
+
+/// Chave usada para as notas sem data de emissão válida no agrupamento por competência.
+pub const SEM_DATA: &str = "sem data";
+
+/// Chave de competência (`aaaa-mm`) da nota, ou `SEM_DATA` quando a data é inválida.
+pub fn chave_competencia(parsed: &ParsedInvoice) -> String {
+    match parsed.data_emissao {
+        Some(data) => data.format("%Y-%m").to_string(),
+        None => SEM_DATA.to_string(),
+    }
+}
+
+/// Agrupa as notas por competência (ano e mês de emissão), em ordem cronológica.
+/// As notas sem data válida ficam num grupo "sem data" ao final.
+pub fn por_competencia(invoices: &[ParsedInvoice], indices: &[usize]) -> Vec<Grupo> {
+    let mut grupos = agrupar(invoices, indices, |parsed| {
+        let nome = match parsed.data_emissao {
+            Some(data) => data.format("%m/%Y").to_string(),
+            None => SEM_DATA.to_string(),
+        };
+        (chave_competencia(parsed), nome)
+    });
+    // A chave `aaaa-mm` ordena cronologicamente; o grupo sem data vai para o fim.
+    grupos.sort_by(|a, b| (a.chave == SEM_DATA, &a.chave).cmp(&(b.chave == SEM_DATA, &b.chave)));
+    grupos
+}
+
+/// Números de nota ausentes na sequência de um prestador.
+pub struct Lacunas {
+    pub cnpj: String,
+    pub nome: String,
+    /// Faixas de números faltantes, com início e fim inclusivos (iguais quando falta uma única nota).
+    pub faixas: Vec<(u32, u32)>,
+}
+
+impl Lacunas {
+    /// Quantidade total de números faltantes.
+    pub fn quantidade(&self) -> u64 {
+        self.faixas.iter().map(|(inicio, fim)| u64::from(fim - inicio) + 1).sum()
+    }
+}
+
+/// Procura buracos na numeração das notas de cada prestador, entre o menor e o maior número presentes.
+/// Apenas os prestadores com alguma lacuna são devolvidos, na ordem de `por_prestador`.
+pub fn lacunas_por_prestador(invoices: &[ParsedInvoice], indices: &[usize]) -> Vec<Lacunas> {
+    por_prestador(invoices, indices)
+        .into_iter()
+        .filter_map(|grupo| {
+            let mut numeros: Vec<u32> = grupo.notas.iter().map(|&i| invoices[i].inf.numero).collect();
+            numeros.sort_unstable();
+            numeros.dedup();
+            let faixas: Vec<(u32, u32)> = numeros
+                .windows(2)
+                .filter(|par| par[1] - par[0] > 1)
+                .map(|par| (par[0] + 1, par[1] - 1))
+                .collect();
+            (!faixas.is_empty()).then_some(Lacunas { cnpj: grupo.chave, nome: grupo.nome, faixas })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+
+    fn nota(cnpj: &str, prestador: &str, numero: u32, valor: f32) -> ParsedInvoice {
+        let xml = format!(
+            "<CompNfse><Nfse><InfNfse><Numero>{numero}</Numero><DataEmissao>2024-01-02T10:00:00</DataEmissao>\
+             <Servico><Valores><ValorServicos>{valor}</ValorServicos></Valores><Discriminacao>Serviço</Discriminacao></Servico>\
+             <PrestadorServico><IdentificacaoPrestador><Cnpj>{cnpj}</Cnpj></IdentificacaoPrestador>\
+             <RazaoSocial>{prestador}</RazaoSocial></PrestadorServico>\
+             <TomadorServico><IdentificacaoTomador><CpfCnpj><Cpf>52998224725</Cpf></CpfCnpj></IdentificacaoTomador>\
+             </TomadorServico></InfNfse></Nfse></CompNfse>"
+        );
+        let inf = crate::parse_xml_bytes(xml.as_bytes(), Path::new("nota.xml")).unwrap().remove(0);
+        ParsedInvoice::new(inf, PathBuf::from("nota.xml"))
+    }
+
+    fn todas(invoices: &[ParsedInvoice]) -> Vec<usize> {
+        (0..invoices.len()).collect()
+    }
+
+    #[test]
+    fn sequencia_continua_nao_tem_lacunas() {
+        let invoices: Vec<_> = [3, 1, 2, 4].into_iter().map(|n| nota("11222333000181", "Alfa", n, 100.0)).collect();
+        assert!(lacunas_por_prestador(&invoices, &todas(&invoices)).is_empty());
+    }
+
+    #[test]
+    fn lacunas_sao_agrupadas_em_faixas_por_prestador() {
+        let mut invoices: Vec<_> = [10, 16, 12, 11].into_iter().map(|n| nota("11222333000181", "Alfa", n, 100.0)).collect();
+        invoices.extend([5, 7].into_iter().map(|n| nota("99888777000166", "Beta", n, 50.0)));
+        // O número 13 de outro prestador não tapa a lacuna do primeiro.
+        invoices.push(nota("99888777000166", "Beta", 13, 50.0));
+
+        let lacunas = lacunas_por_prestador(&invoices, &todas(&invoices));
+        assert_eq!(lacunas.len(), 2);
+        assert_eq!((lacunas[0].cnpj.as_str(), lacunas[0].nome.as_str()), ("11222333000181", "Alfa"));
+        assert_eq!(lacunas[0].faixas, vec![(13, 15)]);
+        assert_eq!(lacunas[0].quantidade(), 3);
+        assert_eq!(lacunas[1].cnpj, "99888777000166");
+        assert_eq!(lacunas[1].faixas, vec![(6, 6), (8, 12)]);
+        assert_eq!(lacunas[1].quantidade(), 6);
+    }
+
+    #[test]
+    fn numeros_duplicados_nao_geram_lacunas() {
+        let invoices: Vec<_> = [1, 2, 2, 3, 3, 3, 5].into_iter().map(|n| nota("11222333000181", "Alfa", n, 100.0)).collect();
+        let lacunas = lacunas_por_prestador(&invoices, &todas(&invoices));
+        assert_eq!(lacunas.len(), 1);
+        assert_eq!(lacunas[0].faixas, vec![(4, 4)]);
+    }
+
+    #[test]
+    fn so_as_notas_indicadas_entram_na_busca() {
+        let invoices: Vec<_> = [1, 2, 3].into_iter().map(|n| nota("11222333000181", "Alfa", n, 100.0)).collect();
+        let lacunas = lacunas_por_prestador(&invoices, &[0, 2]);
+        assert_eq!(lacunas[0].faixas, vec![(2, 2)]);
+    }
+}
@@ -0,0 +1,117 @@
+// Modo de linha de comando: converte XMLs em CSV sem abrir a interface gráfica.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use rayon::prelude::*;
+
+use crate::{export, find_input_files, parse_input_file, DocumentError, ParsedInvoice};
+
+/// Mensagem de uso, com o nome do executável como foi chamado.
+fn usage() -> String {
+    let program = std::env::args().next().unwrap_or_else(|| "rust_proj".to_string());
+    format!("Uso: {} <arquivo ou pasta> --csv <saida.csv>", program)
+}
+
+/// Argumentos aceitos pelo modo de linha de comando.
+struct Args {
+    input: PathBuf,
+    csv: PathBuf,
+}
+
+/// Interpreta os argumentos; o caminho de entrada e `--csv` podem vir em qualquer ordem.
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut input = None;
+    let mut csv = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--csv" => match iter.next() {
+                Some(path) => csv = Some(PathBuf::from(path)),
+                None => return Err("--csv exige o caminho do arquivo de saída".to_string()),
+            },
+            _ if arg.starts_with("--") => return Err(format!("opção desconhecida: {}", arg)),
+            _ if input.is_none() => input = Some(PathBuf::from(arg)),
+            _ => return Err(format!("argumento inesperado: {}", arg)),
+        }
+    }
+    match (input, csv) {
+        (Some(input), Some(csv)) => Ok(Args { input, csv }),
+        (None, _) => Err("informe o arquivo ou a pasta com os XMLs".to_string()),
+        (_, None) => Err("informe o arquivo de saída com --csv".to_string()),
+    }
+}
+
+/// Lista os arquivos a processar: a própria entrada, se for um arquivo, ou os XMLs/ZIPs da pasta.
+fn input_files(input: &Path) -> Vec<PathBuf> {
+    if input.is_dir() {
+        find_input_files(input)
+    } else {
+        vec![input.to_path_buf()]
+    }
+}
+
+/// Executa a conversão em lote. Retorna código de saída diferente de zero se algum arquivo falhar.
+pub fn run(args: &[String]) -> ExitCode {
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", usage());
+        return ExitCode::SUCCESS;
+    }
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Erro: {}\n{}", e, usage());
+            return ExitCode::from(2);
+        }
+    };
+
+    let files = input_files(&args.input);
+    let documents: Vec<_> = files.par_iter().flat_map(parse_input_file).collect();
+
+    // Mesma regra da interface: notas repetidas (número + CNPJ do prestador) entram uma única vez.
+    let mut invoices = Vec::new();
+    let mut seen = HashSet::new();
+    let mut failures = 0;
+    for document in documents {
+        match document.result {
+            Ok(notas) if notas.is_empty() => {
+                eprintln!("{}: arquivo sem notas", document.source.display());
+            }
+            Ok(notas) => {
+                for inf in notas {
+                    let key = (inf.numero, inf.prestador_servico.identificacao_prestador.cnpj.clone());
+                    if seen.insert(key) {
+                        invoices.push(ParsedInvoice::new(inf, document.source.clone()));
+                    }
+                }
+            }
+            // XMLs que não são NFS-e são apenas avisados; não fazem a execução falhar.
+            Err(DocumentError::NotNfse(e)) => {
+                eprintln!("{}: ignorado: {}", document.source.display(), e);
+            }
+            Err(DocumentError::Failed(e)) => {
+                eprintln!("{}: {}", document.source.display(), e);
+                failures += 1;
+            }
+        }
+    }
+
+    if let Err(e) = export::export_csv(&args.csv, &invoices, &export::Coluna::PADRAO) {
+        eprintln!("{}", e);
+        return ExitCode::FAILURE;
+    }
+    eprintln!(
+        "{} nota(s) de {} arquivo(s) exportada(s) para {}",
+        invoices.len(),
+        files.len(),
+        args.csv.display()
+    );
+
+    if failures > 0 {
+        eprintln!("{} documento(s) com erro", failures);
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,122 @@
+// Comparação entre dois lotes de notas (por exemplo, as pastas "enviado" e "recebido").
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use rayon::prelude::*;
+
+use crate::{find_input_files, parse_data_emissao, parse_input_file, InfNfse};
+
+/// Lote em que a nota foi encontrada.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lado {
+    A,
+    B,
+}
+
+/// Nota presente em apenas um dos lotes, identificada pelo número e pelo CNPJ do prestador.
+pub struct Diferenca {
+    pub lado: Lado,
+    pub numero: u32,
+    pub cnpj_prestador: String,
+    pub prestador: String,
+    pub data_emissao: String,
+    pub valor_servicos: f32,
+}
+
+impl Diferenca {
+    fn new(lado: Lado, inf: &InfNfse) -> Self {
+        let data_emissao = match parse_data_emissao(&inf.data_emissao) {
+            Some(data) => data.format("%d/%m/%Y").to_string(),
+            None => inf.data_emissao.clone(),
+        };
+        Self {
+            lado,
+            numero: inf.numero,
+            cnpj_prestador: inf.prestador_servico.identificacao_prestador.cnpj.clone(),
+            prestador: inf.prestador_servico.razao_social.clone(),
+            data_emissao,
+            valor_servicos: inf.servico.valores.valor_servicos,
+        }
+    }
+}
+
+/// Chave de comparação da nota, a mesma usada na detecção de duplicatas.
+fn chave(inf: &InfNfse) -> (u32, &str) {
+    (inf.numero, inf.prestador_servico.identificacao_prestador.cnpj.trim())
+}
+
+/// Lê todas as notas dos XMLs e ZIPs da pasta (ou do arquivo) informada; documentos com erro são ignorados.
+fn carregar(raiz: &Path) -> Vec<InfNfse> {
+    find_input_files(raiz)
+        .par_iter()
+        .flat_map(parse_input_file)
+        .filter_map(|document| document.result.ok())
+        .flatten()
+        .collect()
+}
+
+/// Lista as notas que só existem num dos lotes: primeiro as exclusivas do lote A, depois as do lote B,
+/// cada grupo em ordem de CNPJ do prestador e número.
+pub fn comparar(lote_a: &[InfNfse], lote_b: &[InfNfse]) -> Vec<Diferenca> {
+    let chaves_a: HashSet<(u32, &str)> = lote_a.iter().map(chave).collect();
+    let chaves_b: HashSet<(u32, &str)> = lote_b.iter().map(chave).collect();
+
+    let mut diferencas = Vec::new();
+    let mut vistas = HashSet::new();
+    for (lado, lote, outro) in [(Lado::A, lote_a, &chaves_b), (Lado::B, lote_b, &chaves_a)] {
+        let mut exclusivas: Vec<&InfNfse> = lote
+            .iter()
+            .filter(|inf| !outro.contains(&chave(inf)) && vistas.insert((lado, chave(inf))))
+            .collect();
+        exclusivas.sort_by(|a, b| (chave(a).1, chave(a).0).cmp(&(chave(b).1, chave(b).0)));
+        diferencas.extend(exclusivas.into_iter().map(|inf| Diferenca::new(lado, inf)));
+    }
+    diferencas
+}
+
+/// Estado da janela de comparação: as pastas escolhidas e o resultado, calculado numa thread de fundo.
+#[derive(Default)]
+pub struct Comparacao {
+    pub pasta_a: Option<PathBuf>,
+    pub pasta_b: Option<PathBuf>,
+    receiver: Option<mpsc::Receiver<Vec<Diferenca>>>,
+    pub diferencas: Option<Vec<Diferenca>>,
+}
+
+impl Comparacao {
+    /// Lê as duas pastas e compara as notas numa thread de fundo; o resultado chega por `poll`.
+    pub fn iniciar(&mut self) {
+        let (Some(pasta_a), Some(pasta_b)) = (self.pasta_a.clone(), self.pasta_b.clone()) else {
+            return;
+        };
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let lote_a = carregar(&pasta_a);
+            let lote_b = carregar(&pasta_b);
+            let _ = sender.send(comparar(&lote_a, &lote_b));
+        });
+        self.receiver = Some(receiver);
+        self.diferencas = None;
+    }
+
+    /// Indica se a comparação ainda está em andamento.
+    pub fn em_andamento(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    /// Recebe o resultado da thread de fundo, se já estiver pronto.
+    pub fn poll(&mut self) {
+        let Some(receiver) = &self.receiver else {
+            return;
+        };
+        match receiver.try_recv() {
+            Ok(diferencas) => self.diferencas = Some(diferencas),
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => self.diferencas = Some(Vec::new()),
+        }
+        self.receiver = None;
+    }
+}
@@ -0,0 +1,151 @@
+// Tradução dos erros de leitura do XML para mensagens que o usuário consiga entender e corrigir.
+
+use quick_xml::events::Event;
+use quick_xml::{DeError, Error, Reader};
+
+/// Explicação para documentos truncados, o caso mais comum de XML malformado.
+const XML_INCOMPLETO: &str = "XML incompleto: o arquivo termina antes de todas as tags serem fechadas. \
+    O arquivo pode ter sido copiado ou baixado pela metade; tente obtê-lo novamente";
+
+/// Nome legível das tags que a desserialização exige.
+fn nome_campo(tag: &str) -> Option<&'static str> {
+    let nome = match tag {
+        "Numero" => "número da nota",
+        "DataEmissao" => "data de emissão",
+        "ValorServicos" => "valor dos serviços",
+        "Discriminacao" => "discriminação do serviço",
+        "RazaoSocial" => "razão social",
+        "Cnpj" => "CNPJ do prestador",
+        "CpfCnpj" => "CPF/CNPJ do tomador",
+        "Servico" => "dados do serviço",
+        "Valores" => "valores do serviço",
+        "PrestadorServico" => "dados do prestador",
+        "TomadorServico" => "dados do tomador",
+        "IdentificacaoPrestador" => "identificação do prestador",
+        "IdentificacaoTomador" => "identificação do tomador",
+        "InfNfse" => "dados da nota",
+        "Nfse" => "nota",
+        _ => return None,
+    };
+    Some(nome)
+}
+
+/// Converte a posição em bytes no texto em linha e coluna, ambas começando em 1.
+fn linha_coluna(xml: &str, posicao: usize) -> (usize, usize) {
+    let antes = xml.get(..posicao).unwrap_or(xml);
+    let linha = antes.matches('\n').count() + 1;
+    let coluna = antes.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (linha, coluna)
+}
+
+/// Relê o XML a partir de `inicio` até o primeiro erro de sintaxe e devolve a posição dele.
+fn posicao_erro_sintaxe(xml: &str, inicio: usize) -> Option<usize> {
+    let mut reader = Reader::from_str(xml.get(inicio..)?);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Eof) => return None,
+            Ok(_) => {}
+            Err(_) => return Some(inicio + reader.buffer_position()),
+        }
+    }
+}
+
+/// Procura o conteúdo de texto de um elemento (`>texto<`) a partir de `inicio`.
+fn posicao_texto(xml: &str, inicio: usize, texto: &str) -> Option<usize> {
+    let procurado = format!(">{}<", texto);
+    xml.get(inicio..)?.find(&procurado).map(|p| inicio + p + 1)
+}
+
+/// Extrai o texto entre crases de mensagens do serde como "missing field `Numero`".
+fn entre_crases(mensagem: &str) -> Option<&str> {
+    let resto = &mensagem[mensagem.find('`')? + 1..];
+    Some(&resto[..resto.find('`')?])
+}
+
+/// Explica um erro de sintaxe do XML.
+fn descrever_sintaxe(erro: &Error) -> String {
+    match erro {
+        Error::EndEventMismatch { expected, found } if expected.is_empty() => format!(
+            "XML malformado: a tag </{}> é fechada sem ter sido aberta. Verifique se o arquivo não foi editado manualmente",
+            found
+        ),
+        Error::EndEventMismatch { expected, found } => format!(
+            "XML malformado: a tag <{}> foi fechada com </{}>. Verifique se o arquivo não foi editado manualmente",
+            expected, found
+        ),
+        Error::UnexpectedEof(_) => XML_INCOMPLETO.to_string(),
+        Error::EscapeError(_) => {
+            "XML malformado: há um caractere especial (como &) sem o escape correspondente (&amp;). Peça ao emissor um novo arquivo".to_string()
+        }
+        Error::NonDecodable(_) => {
+            "O arquivo contém caracteres que não correspondem ao encoding declarado no cabeçalho do XML".to_string()
+        }
+        _ => format!("XML malformado: {}. Confira se o arquivo é realmente um XML", erro),
+    }
+}
+
+/// Descreve em português o erro da desserialização, com o provável campo envolvido e uma sugestão.
+/// `xml` é o documento lido e `inicio`, a posição nele do trecho que foi desserializado; com eles
+/// a mensagem indica a linha e a coluna do problema quando é possível localizá-lo.
+pub fn descrever_erro(erro: &DeError, xml: &str, inicio: usize) -> String {
+    let mut posicao = None;
+    let descricao = match erro {
+        DeError::Custom(mensagem) if mensagem.starts_with("missing field") => match entre_crases(mensagem) {
+            Some("ListaNfse") => "O arquivo não parece ser uma NFS-e: não foi encontrada a lista de notas (<ListaNfse>) nem uma nota isolada (<CompNfse> ou <Nfse>). Confira se o XML escolhido é de notas fiscais de serviço".to_string(),
+            Some(tag) => {
+                let nome = nome_campo(tag).map(|n| format!("{} ", n)).unwrap_or_default();
+                format!(
+                    "Campo obrigatório ausente: {}(<{}>). Verifique se o XML é uma NFS-e completa e se a tag não foi removida ou renomeada",
+                    nome, tag
+                )
+            }
+            None => format!("Campo obrigatório ausente: {}", mensagem),
+        },
+        DeError::Custom(mensagem) if mensagem.starts_with("duplicate field") => format!(
+            "O campo <{}> aparece mais de uma vez no mesmo bloco. Verifique se o arquivo não foi montado juntando notas diferentes",
+            entre_crases(mensagem).unwrap_or("?")
+        ),
+        DeError::Custom(mensagem) if mensagem.starts_with("valor numérico inválido") => {
+            // A mensagem traz o texto recusado entre aspas, o que permite localizá-lo no XML.
+            let texto = mensagem.split('"').nth(1).unwrap_or("");
+            posicao = posicao_texto(xml, inicio, texto);
+            format!(
+                "Valor em formato inesperado: \"{}\" não é um número. Os valores devem usar apenas dígitos e separador decimal, como 1234.56 ou 1.234,56",
+                texto
+            )
+        }
+        DeError::Custom(mensagem) if mensagem.starts_with("invalid") => format!(
+            "Um campo tem conteúdo em formato inesperado ({}). Confira se os campos numéricos e de data estão preenchidos corretamente",
+            mensagem
+        ),
+        DeError::InvalidInt(_) => {
+            "O número da nota (<Numero>) deve conter apenas dígitos. Confira se o campo não está vazio ou com letras".to_string()
+        }
+        DeError::InvalidFloat(_) => {
+            "Um valor numérico está em formato inesperado. Os valores devem usar apenas dígitos e separador decimal".to_string()
+        }
+        DeError::InvalidXml(e) => {
+            posicao = posicao_erro_sintaxe(xml, inicio);
+            descrever_sintaxe(e)
+        }
+        DeError::UnexpectedEof => {
+            // Sem erro de sintaxe antes do fim, o problema está no ponto em que o documento acaba.
+            posicao = posicao_erro_sintaxe(xml, inicio).or(Some(xml.len()));
+            XML_INCOMPLETO.to_string()
+        }
+        DeError::UnexpectedStart(tag) => format!(
+            "A tag <{}> apareceu dentro de um campo que deveria conter apenas texto. Verifique a estrutura do XML",
+            String::from_utf8_lossy(tag)
+        ),
+        DeError::ExpectedStart => "O arquivo não contém nenhum elemento XML. Verifique se ele não está vazio".to_string(),
+        _ => format!("Erro inesperado ao ler o XML: {}", erro),
+    };
+
+    match posicao {
+        Some(posicao) => {
+            let (linha, coluna) = linha_coluna(xml, posicao);
+            format!("{} (linha {}, coluna {}). Detalhe técnico: {}", descricao, linha, coluna, erro)
+        }
+        None => format!("{}. Detalhe técnico: {}", descricao, erro),
+    }
+}
@@ -0,0 +1,103 @@
+// Validação de documentos de identificação (CPF e CNPJ).
+
+/// Extrai apenas os dígitos do documento, ignorando pontuação e espaços.
+fn digitos(documento: &str) -> Vec<u32> {
+    documento.chars().filter_map(|c| c.to_digit(10)).collect()
+}
+
+/// Calcula um dígito verificador no módulo 11 a partir dos pesos informados.
+fn digito_verificador(digitos: &[u32], pesos: &[u32]) -> u32 {
+    let soma: u32 = digitos.iter().zip(pesos).map(|(d, p)| d * p).sum();
+    let resto = soma % 11;
+    if resto < 2 {
+        0
+    } else {
+        11 - resto
+    }
+}
+
+/// Documentos com todos os dígitos iguais passam no cálculo, mas não são válidos.
+fn todos_iguais(digitos: &[u32]) -> bool {
+    digitos.windows(2).all(|par| par[0] == par[1])
+}
+
+/// Valida os dígitos verificadores de um CPF (11 dígitos).
+pub fn validar_cpf(cpf: &str) -> bool {
+    let digitos = digitos(cpf);
+    if digitos.len() != 11 || todos_iguais(&digitos) {
+        return false;
+    }
+    let primeiro = digito_verificador(&digitos[..9], &[10, 9, 8, 7, 6, 5, 4, 3, 2]);
+    let segundo = digito_verificador(&digitos[..10], &[11, 10, 9, 8, 7, 6, 5, 4, 3, 2]);
+    digitos[9] == primeiro && digitos[10] == segundo
+}
+
+/// Valida os dígitos verificadores de um CNPJ (14 dígitos).
+pub fn validar_cnpj(cnpj: &str) -> bool {
+    let digitos = digitos(cnpj);
+    if digitos.len() != 14 || todos_iguais(&digitos) {
+        return false;
+    }
+    let primeiro = digito_verificador(&digitos[..12], &[5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2]);
+    let segundo = digito_verificador(&digitos[..13], &[6, 5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2]);
+    digitos[12] == primeiro && digitos[13] == segundo
+}
+
+/// Aplica a máscara de CNPJ (`00.000.000/0000-00`) ou CPF (`000.000.000-00`) conforme a quantidade de dígitos.
+/// Documentos com outro tamanho são devolvidos sem alteração.
+pub fn formatar_documento(documento: &str) -> String {
+    let d: String = documento.chars().filter(char::is_ascii_digit).collect();
+    match d.len() {
+        14 => format!("{}.{}.{}/{}-{}", &d[..2], &d[2..5], &d[5..8], &d[8..12], &d[12..]),
+        11 => format!("{}.{}.{}-{}", &d[..3], &d[3..6], &d[6..9], &d[9..]),
+        _ => documento.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpf_valido_com_e_sem_mascara() {
+        assert!(validar_cpf("52998224725"));
+        assert!(validar_cpf("529.982.247-25"));
+    }
+
+    #[test]
+    fn cpf_com_digito_verificador_errado() {
+        assert!(!validar_cpf("52998224724"));
+        assert!(!validar_cpf("52998224715"));
+    }
+
+    #[test]
+    fn cpf_com_digitos_repetidos_ou_tamanho_errado() {
+        assert!(!validar_cpf("00000000000"));
+        assert!(!validar_cpf("111.111.111-11"));
+        assert!(!validar_cpf("5299822472"));
+        assert!(!validar_cpf("529982247250"));
+        assert!(!validar_cpf(""));
+    }
+
+    #[test]
+    fn cnpj_valido_com_e_sem_mascara() {
+        assert!(validar_cnpj("11222333000181"));
+        assert!(validar_cnpj("11.222.333/0001-81"));
+    }
+
+    #[test]
+    fn cnpj_com_digito_verificador_errado() {
+        assert!(!validar_cnpj("11222333000182"));
+        assert!(!validar_cnpj("11222333000191"));
+    }
+
+    #[test]
+    fn cnpj_com_digitos_repetidos_ou_tamanho_errado() {
+        assert!(!validar_cnpj("00000000000000"));
+        assert!(!validar_cnpj("99.999.999/9999-99"));
+        assert!(!validar_cnpj("1122233300018"));
+        assert!(!validar_cnpj("112223330001810"));
+        // Um CPF válido não é um CNPJ.
+        assert!(!validar_cnpj("52998224725"));
+    }
+}
@@ -0,0 +1,611 @@
+// Funções de exportação das notas processadas para outros formatos.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::agrupamento::Grupo;
+use crate::comparacao::{Diferenca, Lado};
+use crate::formato;
+use crate::{parse_data_emissao, FileError, InfNfse, ParsedInvoice};
+
+/// Separador de campos do CSV (padrão do Excel em português).
+const CSV_SEPARATOR: char = ';';
+
+/// Coluna que pode ser incluída nas exportações CSV e XLSX.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Coluna {
+    Numero,
+    DataEmissao,
+    DiaSemana,
+    Competencia,
+    Prestador,
+    CnpjPrestador,
+    Tomador,
+    DocumentoTomador,
+    ValorServicos,
+    ValorIss,
+    ValorLiquido,
+    Discriminacao,
+    Anotacao,
+}
+
+impl Coluna {
+    /// Todas as colunas, na ordem em que aparecem no diálogo de exportação.
+    pub const TODAS: [Coluna; 13] = [
+        Coluna::Numero,
+        Coluna::DataEmissao,
+        Coluna::DiaSemana,
+        Coluna::Competencia,
+        Coluna::Prestador,
+        Coluna::CnpjPrestador,
+        Coluna::Tomador,
+        Coluna::DocumentoTomador,
+        Coluna::ValorServicos,
+        Coluna::ValorIss,
+        Coluna::ValorLiquido,
+        Coluna::Discriminacao,
+        Coluna::Anotacao,
+    ];
+
+    /// Colunas das exportações sem escolha de colunas, na ordem em que aparecem no arquivo.
+    pub const PADRAO: [Coluna; 13] = [
+        Coluna::Numero,
+        Coluna::DataEmissao,
+        Coluna::DiaSemana,
+        Coluna::Competencia,
+        Coluna::Prestador,
+        Coluna::CnpjPrestador,
+        Coluna::Tomador,
+        Coluna::DocumentoTomador,
+        Coluna::ValorServicos,
+        Coluna::ValorIss,
+        Coluna::ValorLiquido,
+        Coluna::Discriminacao,
+        Coluna::Anotacao,
+    ];
+
+    /// Título da coluna no cabeçalho do arquivo.
+    pub fn titulo(self) -> &'static str {
+        match self {
+            Coluna::Numero => "Número",
+            Coluna::DataEmissao => "Data de Emissão",
+            Coluna::DiaSemana => "Dia da Semana",
+            Coluna::Competencia => "Competência",
+            Coluna::Prestador => "Prestador",
+            Coluna::CnpjPrestador => "CNPJ Prestador",
+            Coluna::Tomador => "Tomador",
+            Coluna::DocumentoTomador => "CPF/CNPJ Tomador",
+            Coluna::ValorServicos => "Valor dos Serviços",
+            Coluna::ValorIss => "Valor do ISS",
+            Coluna::ValorLiquido => "Valor Líquido",
+            Coluna::Discriminacao => "Discriminação",
+            Coluna::Anotacao => "Anotação",
+        }
+    }
+
+    /// Conteúdo da coluna para a nota, como texto; valores opcionais ausentes ficam vazios.
+    fn texto(self, parsed: &ParsedInvoice) -> String {
+        let invoice = &parsed.inf;
+        let valores = &invoice.servico.valores;
+        match self {
+            Coluna::Numero => invoice.numero.to_string(),
+            Coluna::DataEmissao => parsed.data_emissao_formatada(),
+            Coluna::DiaSemana => parsed.dia_semana().to_string(),
+            Coluna::Competencia => parsed.competencia(),
+            Coluna::Prestador => invoice.prestador_servico.razao_social.clone(),
+            Coluna::CnpjPrestador => invoice.prestador_servico.identificacao_prestador.cnpj.clone(),
+            Coluna::Tomador => invoice.tomador_servico.nome().unwrap_or("").to_string(),
+            Coluna::DocumentoTomador => documento_tomador(invoice).to_string(),
+            Coluna::ValorServicos => format_decimal(valores.valor_servicos),
+            Coluna::ValorIss => valores.valor_iss.map(format_decimal).unwrap_or_default(),
+            Coluna::ValorLiquido => valores.valor_liquido().map(format_decimal).unwrap_or_default(),
+            Coluna::Discriminacao => invoice.servico.discriminacao.clone(),
+            Coluna::Anotacao => parsed.anotacao.clone(),
+        }
+    }
+}
+
+/// Coloca o campo entre aspas quando ele contém o separador, aspas ou quebras de linha.
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([CSV_SEPARATOR, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Formata um valor decimal com vírgula, como o Excel brasileiro espera.
+fn format_decimal(value: impl Into<f64>) -> String {
+    format!("{:.2}", value.into()).replace('.', ",")
+}
+
+/// Retorna o CPF ou CNPJ do tomador, o que estiver presente; para tomador no exterior, o NIF.
+fn documento_tomador(invoice: &InfNfse) -> &str {
+    invoice.tomador_servico.identificacao_tomador.documento().unwrap_or("")
+}
+
+/// Monta uma linha separada por tabulação com os campos da nota, pronta para colar numa planilha.
+/// Os valores usam vírgula decimal, como o Excel brasileiro espera.
+pub fn tsv_row(parsed: &ParsedInvoice, colunas: &[Coluna]) -> String {
+    // Tabulações e quebras de linha dentro dos campos quebrariam as colunas ao colar.
+    colunas
+        .iter()
+        .map(|coluna| coluna.texto(parsed).replace(['\t', '\r', '\n'], " "))
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+/// Monta uma tabela separada por tabulação, com cabeçalho e uma linha por nota, para colar numa planilha.
+pub fn tsv_table(invoices: &[&ParsedInvoice], colunas: &[Coluna]) -> String {
+    let titulos: Vec<&str> = colunas.iter().map(|coluna| coluna.titulo()).collect();
+    let mut lines = vec![titulos.join("\t")];
+    lines.extend(invoices.iter().map(|parsed| tsv_row(parsed, colunas)));
+    lines.join("\n")
+}
+
+/// Monta uma linha do CSV a partir dos campos já convertidos em texto.
+fn csv_line(fields: &[&str]) -> String {
+    let escaped: Vec<String> = fields.iter().map(|f| escape_csv_field(f)).collect();
+    let mut line = escaped.join(&CSV_SEPARATOR.to_string());
+    line.push_str("\r\n");
+    line
+}
+
+/// Exporta as notas para um arquivo CSV separado por `;`, com as colunas informadas, na ordem dada.
+pub fn export_csv(path: &Path, invoices: &[ParsedInvoice], colunas: &[Coluna]) -> Result<(), String> {
+    // O BOM faz o Excel reconhecer o arquivo como UTF-8 e exibir os acentos corretamente.
+    let mut contents = String::from("\u{feff}");
+    let titulos: Vec<&str> = colunas.iter().map(|coluna| coluna.titulo()).collect();
+    contents.push_str(&csv_line(&titulos));
+
+    for parsed in invoices {
+        let campos: Vec<String> = colunas.iter().map(|coluna| coluna.texto(parsed)).collect();
+        let campos: Vec<&str> = campos.iter().map(String::as_str).collect();
+        contents.push_str(&csv_line(&campos));
+    }
+
+    fs::write(path, contents)
+        .map_err(|e| format!("Erro ao salvar o arquivo \"{}\": {}", path.display(), e))
+}
+
+/// Exporta o resumo de um agrupamento: uma linha por grupo com chave, nome, quantidade de notas, total e total de ISS.
+/// `headers` traz os títulos das colunas de chave e de nome, que dependem do critério de agrupamento;
+/// com um único título, a coluna de nome é omitida.
+pub fn export_groups_csv(path: &Path, headers: &[&str], grupos: &[Grupo]) -> Result<(), String> {
+    let with_name = headers.len() > 1;
+    let mut contents = String::from("\u{feff}");
+    let mut header_line = headers.to_vec();
+    header_line.extend(["Quantidade de Notas", "Total", "Total ISS"]);
+    contents.push_str(&csv_line(&header_line));
+    for grupo in grupos {
+        let quantidade = grupo.notas.len().to_string();
+        let total = format_decimal(grupo.total);
+        let total_iss = format_decimal(grupo.total_iss);
+        let mut fields = vec![grupo.chave.as_str()];
+        if with_name {
+            fields.push(&grupo.nome);
+        }
+        fields.extend([quantidade.as_str(), total.as_str(), total_iss.as_str()]);
+        contents.push_str(&csv_line(&fields));
+    }
+
+    fs::write(path, contents)
+        .map_err(|e| format!("Erro ao salvar o arquivo \"{}\": {}", path.display(), e))
+}
+
+/// Exporta a relação dos arquivos problemáticos do lote, para repassar a quem gerou os XMLs: uma linha por arquivo,
+/// com a categoria (erro, sem notas ou ignorado), o caminho e a mensagem.
+pub fn export_erros_csv(
+    path: &Path,
+    erros: &[FileError],
+    sem_notas: &[PathBuf],
+    ignorados: &[FileError],
+) -> Result<(), String> {
+    let mut contents = String::from("\u{feff}");
+    contents.push_str(&csv_line(&["Categoria", "Arquivo", "Mensagem"]));
+    for erro in erros {
+        contents.push_str(&csv_line(&["Erro", &erro.path.display().to_string(), &erro.message]));
+    }
+    for arquivo in sem_notas {
+        contents.push_str(&csv_line(&[
+            "Sem notas",
+            &arquivo.display().to_string(),
+            "O arquivo não contém nenhuma nota (CompNfse)",
+        ]));
+    }
+    for ignorado in ignorados {
+        contents.push_str(&csv_line(&["Ignorado", &ignorado.path.display().to_string(), &ignorado.message]));
+    }
+
+    fs::write(path, contents)
+        .map_err(|e| format!("Erro ao salvar o arquivo \"{}\": {}", path.display(), e))
+}
+
+/// Exporta a comparação entre dois lotes: uma linha por nota presente em apenas um deles.
+pub fn export_diferencas_csv(path: &Path, diferencas: &[Diferenca]) -> Result<(), String> {
+    let mut contents = String::from("\u{feff}");
+    contents.push_str(&csv_line(&[
+        "Situação",
+        "Número",
+        "CNPJ Prestador",
+        "Prestador",
+        "Data de Emissão",
+        "Valor dos Serviços",
+    ]));
+    for diferenca in diferencas {
+        let situacao = match diferenca.lado {
+            Lado::A => "Só no lote A",
+            Lado::B => "Só no lote B",
+        };
+        contents.push_str(&csv_line(&[
+            situacao,
+            &diferenca.numero.to_string(),
+            &diferenca.cnpj_prestador,
+            &diferenca.prestador,
+            &diferenca.data_emissao,
+            &format_decimal(diferenca.valor_servicos),
+        ]));
+    }
+
+    fs::write(path, contents)
+        .map_err(|e| format!("Erro ao salvar o arquivo \"{}\": {}", path.display(), e))
+}
+
+/// Campo do TXT posicional: texto alinhado à esquerda e completado com espaços,
+/// ou número alinhado à direita e completado com zeros.
+enum Campo<'a> {
+    Texto(&'a str, usize),
+    Numero(u64, usize),
+}
+
+impl Campo<'_> {
+    /// Formata o campo com exatamente a largura definida, truncando o que passar dela.
+    fn formatar(&self) -> String {
+        match *self {
+            // Sem acentos e só com ASCII, cada caractere ocupa uma posição em qualquer encoding.
+            Campo::Texto(texto, largura) => {
+                let ascii: String = texto
+                    .nfd()
+                    .filter(|c| !is_combining_mark(*c))
+                    .map(|c| if c.is_ascii() && !c.is_ascii_control() { c } else { ' ' })
+                    .take(largura)
+                    .collect();
+                format!("{:<largura$}", ascii, largura = largura)
+            }
+            Campo::Numero(numero, largura) => {
+                let texto = format!("{:0largura$}", numero, largura = largura);
+                texto[texto.len() - largura..].to_string()
+            }
+        }
+    }
+}
+
+/// Converte os dígitos de um documento em número; documentos ausentes viram zero.
+fn digitos(documento: &str) -> u64 {
+    documento.chars().filter(char::is_ascii_digit).collect::<String>().parse().unwrap_or(0)
+}
+
+/// Converte um valor em reais para centavos; valores negativos viram zero.
+fn centavos(valor: f32) -> u64 {
+    formato::centavos(valor).max(0) as u64
+}
+
+/// Exporta as notas para o TXT posicional usado na importação da contabilidade, uma linha por nota,
+/// com 212 posições e quebra de linha CRLF:
+///
+/// | Posição | Tamanho | Campo                                      | Preenchimento         |
+/// |---------|---------|--------------------------------------------|-----------------------|
+/// | 1       | 10      | Número da nota                             | zeros à esquerda      |
+/// | 11      | 8       | Data de emissão (`AAAAMMDD`)               | zeros se inválida     |
+/// | 19      | 14      | CNPJ do prestador                          | zeros à esquerda      |
+/// | 33      | 60      | Razão social do prestador                  | espaços à direita     |
+/// | 93      | 14      | CPF/CNPJ do tomador                        | zeros à esquerda      |
+/// | 107     | 60      | Razão social do tomador                    | espaços à direita     |
+/// | 167     | 15      | Valor dos serviços, em centavos            | zeros à esquerda      |
+/// | 182     | 15      | Valor do ISS, em centavos                  | zeros se ausente      |
+/// | 197     | 15      | Valor líquido, em centavos                 | zeros se ausente      |
+/// | 212     | 1       | Situação: `N` normal, `C` cancelada        |                       |
+///
+/// Textos são gravados sem acentos e truncados no tamanho do campo.
+pub fn export_txt_posicional(path: &Path, invoices: &[InfNfse]) -> Result<(), String> {
+    let mut contents = String::new();
+    for invoice in invoices {
+        let data = parse_data_emissao(&invoice.data_emissao)
+            .map(|data| data.format("%Y%m%d").to_string())
+            .unwrap_or_else(|| "0".repeat(8));
+        let valores = &invoice.servico.valores;
+        // O campo é numérico: o NIF de tomadores no exterior não entra, e o campo fica zerado.
+        let cpf_cnpj_tomador = invoice.tomador_servico.identificacao_tomador.cpf_cnpj.documento().unwrap_or("");
+        let campos = [
+            Campo::Numero(u64::from(invoice.numero), 10),
+            Campo::Texto(&data, 8),
+            Campo::Numero(digitos(&invoice.prestador_servico.identificacao_prestador.cnpj), 14),
+            Campo::Texto(&invoice.prestador_servico.razao_social, 60),
+            Campo::Numero(digitos(cpf_cnpj_tomador), 14),
+            Campo::Texto(invoice.tomador_servico.nome().unwrap_or(""), 60),
+            Campo::Numero(centavos(valores.valor_servicos), 15),
+            Campo::Numero(valores.valor_iss.map_or(0, centavos), 15),
+            Campo::Numero(valores.valor_liquido().map_or(0, centavos), 15),
+            Campo::Texto(if invoice.cancelada { "C" } else { "N" }, 1),
+        ];
+        for campo in &campos {
+            contents.push_str(&campo.formatar());
+        }
+        contents.push_str("\r\n");
+    }
+
+    fs::write(path, contents)
+        .map_err(|e| format!("Erro ao salvar o arquivo \"{}\": {}", path.display(), e))
+}
+
+/// Define o arquivo de cada nota na divisão em XMLs individuais: `nfse_<número>.xml` dentro de `pasta`.
+/// Se o número se repetir (prestadores diferentes), o CNPJ do prestador entra no nome.
+/// Notas sem o trecho de XML original são deixadas de fora.
+pub fn xml_individual_paths<'a>(pasta: &Path, invoices: &'a [ParsedInvoice]) -> Vec<(PathBuf, &'a InfNfse)> {
+    let mut nomes = HashSet::new();
+    invoices
+        .iter()
+        .map(|parsed| &parsed.inf)
+        .filter(|invoice| !invoice.xml.is_empty())
+        .map(|invoice| {
+            let mut nome = format!("nfse_{}.xml", invoice.numero);
+            if !nomes.insert(nome.clone()) {
+                let cnpj: String = invoice
+                    .prestador_servico
+                    .identificacao_prestador
+                    .cnpj
+                    .chars()
+                    .filter(char::is_ascii_digit)
+                    .collect();
+                nome = format!("nfse_{}_{}.xml", invoice.numero, cnpj);
+                nomes.insert(nome.clone());
+            }
+            (pasta.join(nome), invoice)
+        })
+        .collect()
+}
+
+/// Grava cada nota no seu arquivo, com o trecho `CompNfse` original precedido da declaração XML.
+/// O trecho já foi decodificado na leitura, então os arquivos são gravados em UTF-8.
+pub fn export_xml_individuais(arquivos: &[(PathBuf, &InfNfse)]) -> Result<(), String> {
+    for (path, invoice) in arquivos {
+        let contents = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}\n", invoice.xml);
+        fs::write(path, contents).map_err(|e| format!("Erro ao salvar o arquivo \"{}\": {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Exporta as notas para um arquivo JSON, mantendo a hierarquia do XML (prestador, tomador, serviço e valores).
+/// Campos ausentes na nota são omitidos.
+pub fn export_json(path: &Path, invoices: &[ParsedInvoice]) -> Result<(), String> {
+    let notas: Vec<&InfNfse> = invoices.iter().map(|p| &p.inf).collect();
+    let file = fs::File::create(path)
+        .map_err(|e| format!("Erro ao salvar o arquivo \"{}\": {}", path.display(), e))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &notas)
+        .map_err(|e| format!("Erro ao gerar o JSON \"{}\": {}", path.display(), e))
+}
+
+/// Exporta as notas para uma planilha XLSX, com as colunas informadas, na ordem dada.
+pub fn export_xlsx(path: &Path, invoices: &[ParsedInvoice], colunas: &[Coluna]) -> Result<(), String> {
+    write_xlsx(path, invoices, colunas)
+        .map_err(|e| format!("Erro ao salvar a planilha \"{}\": {}", path.display(), e))
+}
+
+/// Formatos de célula usados na planilha de notas.
+struct FormatosXlsx {
+    /// Documentos são gravados como texto para preservar os zeros à esquerda.
+    texto: Format,
+    moeda: Format,
+    data: Format,
+}
+
+fn write_xlsx(path: &Path, invoices: &[ParsedInvoice], colunas: &[Coluna]) -> Result<(), XlsxError> {
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+
+    let header_format = Format::new().set_bold();
+    let formatos = FormatosXlsx {
+        texto: Format::new().set_num_format("@"),
+        moeda: Format::new().set_num_format("\"R$\" #,##0.00"),
+        data: Format::new().set_num_format("dd/mm/yyyy"),
+    };
+
+    for (col, coluna) in colunas.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, coluna.titulo(), &header_format)?;
+    }
+
+    for (i, parsed) in invoices.iter().enumerate() {
+        let row = i as u32 + 1;
+        for (col, coluna) in colunas.iter().enumerate() {
+            write_xlsx_cell(worksheet, row, col as u16, *coluna, parsed, &formatos)?;
+        }
+    }
+
+    worksheet.set_freeze_panes(1, 0)?;
+    worksheet.autofit();
+
+    workbook.save(path)
+}
+
+/// Valor em reais como `f64` exato ao centavo, para as células numéricas da planilha.
+fn reais(valor: f32) -> f64 {
+    formato::centavos(valor) as f64 / 100.0
+}
+
+/// Grava uma célula da planilha com o tipo adequado à coluna: números, datas e valores ficam utilizáveis
+/// em fórmulas, e valores opcionais ausentes deixam a célula vazia.
+fn write_xlsx_cell(
+    worksheet: &mut Worksheet,
+    row: u32,
+    col: u16,
+    coluna: Coluna,
+    parsed: &ParsedInvoice,
+    formatos: &FormatosXlsx,
+) -> Result<(), XlsxError> {
+    let invoice = &parsed.inf;
+    let valores = &invoice.servico.valores;
+    match coluna {
+        Coluna::Numero => {
+            worksheet.write_number(row, col, invoice.numero)?;
+        }
+        // Datas válidas viram datas do Excel; as demais ficam como o texto original.
+        Coluna::DataEmissao => match parsed.data_emissao {
+            Some(data) => {
+                worksheet.write_datetime_with_format(row, col, data, &formatos.data)?;
+            }
+            None => {
+                worksheet.write_string(row, col, &invoice.data_emissao)?;
+            }
+        },
+        Coluna::CnpjPrestador | Coluna::DocumentoTomador => {
+            worksheet.write_string_with_format(row, col, coluna.texto(parsed), &formatos.texto)?;
+        }
+        // Os valores passam pelos centavos: convertido direto, o f32 chegaria à planilha como 1234.56005859375.
+        Coluna::ValorServicos => {
+            worksheet.write_number_with_format(row, col, reais(valores.valor_servicos), &formatos.moeda)?;
+        }
+        Coluna::ValorIss | Coluna::ValorLiquido => {
+            let valor = if coluna == Coluna::ValorIss { valores.valor_iss } else { valores.valor_liquido() };
+            if let Some(valor) = valor {
+                worksheet.write_number_with_format(row, col, reais(valor), &formatos.moeda)?;
+            }
+        }
+        _ => {
+            let texto = coluna.texto(parsed);
+            if !texto.is_empty() {
+                worksheet.write_string(row, col, texto)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lê uma nota a partir do XML de um `CompNfse`, como o processamento faria.
+    fn nota(numero: u32, data: &str, valores: &str, prestador: &str, tomador: &str, documento: &str, extra: &str) -> InfNfse {
+        let xml = format!(
+            "<CompNfse><Nfse><InfNfse><Numero>{numero}</Numero><DataEmissao>{data}</DataEmissao>\
+             <Servico><Valores>{valores}</Valores><Discriminacao>Serviço</Discriminacao></Servico>\
+             <PrestadorServico><IdentificacaoPrestador><Cnpj>11.222.333/0001-81</Cnpj></IdentificacaoPrestador>\
+             <RazaoSocial>{prestador}</RazaoSocial></PrestadorServico>\
+             <TomadorServico><IdentificacaoTomador><CpfCnpj>{documento}</CpfCnpj></IdentificacaoTomador>\
+             <RazaoSocial>{tomador}</RazaoSocial></TomadorServico></InfNfse></Nfse>{extra}</CompNfse>"
+        );
+        crate::parse_xml_bytes(xml.as_bytes(), Path::new("nota.xml")).unwrap().remove(0)
+    }
+
+    #[test]
+    fn campo_csv_sem_caracteres_especiais_fica_como_esta() {
+        assert_eq!(escape_csv_field("Serviço de manutenção, mensal"), "Serviço de manutenção, mensal");
+        assert_eq!(escape_csv_field(""), "");
+    }
+
+    #[test]
+    fn campo_csv_com_separador_vai_entre_aspas() {
+        assert_eq!(escape_csv_field("Horas; materiais"), "\"Horas; materiais\"");
+    }
+
+    #[test]
+    fn campo_csv_com_aspas_as_duplica() {
+        assert_eq!(escape_csv_field("Projeto \"Centro\""), "\"Projeto \"\"Centro\"\"\"");
+        assert_eq!(escape_csv_field("\""), "\"\"\"\"");
+    }
+
+    #[test]
+    fn campo_csv_com_quebra_de_linha_vai_entre_aspas() {
+        assert_eq!(escape_csv_field("linha 1\nlinha 2"), "\"linha 1\nlinha 2\"");
+        assert_eq!(escape_csv_field("linha 1\r\nlinha 2"), "\"linha 1\r\nlinha 2\"");
+    }
+
+    #[test]
+    fn txt_posicional_respeita_o_layout_documentado() {
+        let notas = [
+            nota(
+                123,
+                "2024-01-02T10:00:00",
+                "<ValorServicos>1234.56</ValorServicos><ValorIss>61.73</ValorIss>",
+                "Ângela Serviços Ltda",
+                "Associação dos Moradores e Amigos do Condomínio Residencial São Conrado",
+                "<Cpf>529.982.247-25</Cpf>",
+                "",
+            ),
+            nota(
+                7,
+                "data inválida",
+                "<ValorServicos>80</ValorServicos>",
+                "Prestador",
+                "Café €uro",
+                "<Cnpj>11222333000181</Cnpj>",
+                "<NfseCancelamento><Confirmacao/></NfseCancelamento>",
+            ),
+        ];
+        let path = std::env::temp_dir().join(format!("nfse_txt_posicional_{}.txt", std::process::id()));
+        export_txt_posicional(&path, &notas).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(contents.ends_with("\r\n"));
+        let linhas: Vec<&str> = contents.split_terminator("\r\n").collect();
+        assert_eq!(linhas.len(), 2);
+        for linha in &linhas {
+            assert!(linha.is_ascii());
+            assert_eq!(linha.len(), 212);
+        }
+
+        // Posições da tabela de `export_txt_posicional`, a partir de zero.
+        fn campos(linha: &str) -> [&str; 10] {
+            [
+                &linha[0..10],
+                &linha[10..18],
+                &linha[18..32],
+                &linha[32..92],
+                &linha[92..106],
+                &linha[106..166],
+                &linha[166..181],
+                &linha[181..196],
+                &linha[196..211],
+                &linha[211..212],
+            ]
+        }
+        assert_eq!(
+            campos(linhas[0]),
+            [
+                "0000000123",
+                "20240102",
+                "11222333000181",
+                "Angela Servicos Ltda                                        ",
+                "00052998224725",
+                "Associacao dos Moradores e Amigos do Condominio Residencial ",
+                "000000000123456",
+                "000000000006173",
+                "000000000117283",
+                "N",
+            ]
+        );
+        assert_eq!(
+            campos(linhas[1]),
+            [
+                "0000000007",
+                "00000000",
+                "11222333000181",
+                "Prestador                                                   ",
+                "11222333000181",
+                "Cafe  uro                                                   ",
+                "000000000008000",
+                "000000000000000",
+                "000000000000000",
+                "C",
+            ]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+// Funções de formatação de valores para exibição.
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Converte um valor em reais para centavos, arredondando ao centavo mais próximo.
+pub fn centavos(valor: f32) -> i64 {
+    (f64::from(valor) * 100.0).round() as i64
+}
+
+/// Soma valores monetários em centavos inteiros e devolve o total em reais.
+/// Somar os `f32` diretamente acumularia erro de arredondamento e, em lotes grandes, erraria os centavos.
+pub fn somar_valores<I: IntoIterator<Item = f32>>(valores: I) -> f64 {
+    valores.into_iter().map(centavos).sum::<i64>() as f64 / 100.0
+}
+
+/// Média e desvio padrão (populacional) dos valores; `None` com menos de dois valores.
+pub fn media_desvio<I: IntoIterator<Item = f32>>(valores: I) -> Option<(f64, f64)> {
+    let valores: Vec<f64> = valores.into_iter().map(f64::from).collect();
+    if valores.len() < 2 {
+        return None;
+    }
+    let n = valores.len() as f64;
+    let media = valores.iter().sum::<f64>() / n;
+    let variancia = valores.iter().map(|v| (v - media).powi(2)).sum::<f64>() / n;
+    Some((media, variancia.sqrt()))
+}
+
+/// Formata um valor monetário no padrão brasileiro, por exemplo `R$ 1.234,56`.
+/// Aceita tanto os valores das notas (`f32`) quanto os totais (`f64`).
+pub fn formatar_moeda(valor: impl Into<f64>) -> String {
+    let centavos = (valor.into() * 100.0).round() as i64;
+    let sinal = if centavos < 0 { "-" } else { "" };
+    let centavos = centavos.unsigned_abs();
+
+    // Agrupa a parte inteira de três em três dígitos com ponto.
+    let inteiro = (centavos / 100).to_string();
+    let mut agrupado = String::with_capacity(inteiro.len() + inteiro.len() / 3);
+    for (i, digito) in inteiro.chars().enumerate() {
+        if i > 0 && (inteiro.len() - i).is_multiple_of(3) {
+            agrupado.push('.');
+        }
+        agrupado.push(digito);
+    }
+
+    format!("{}R$ {},{:02}", sinal, agrupado, centavos % 100)
+}
+
+/// Formata a alíquota do ISS como percentual.
+/// A Nota Carioca informa a alíquota como fração (`0.05`), mas alguns emissores já enviam o percentual (`5.00`).
+pub fn formatar_aliquota(aliquota: f32) -> String {
+    let percentual = if aliquota <= 1.0 { aliquota * 100.0 } else { aliquota };
+    format!("{:.2}%", percentual).replace('.', ",")
+}
+
+/// Interpreta um valor digitado pelo usuário, aceitando `1.234,56`, `1234,56`, `1234.56` e o prefixo `R$`.
+/// Retorna `None` para textos vazios ou inválidos.
+pub fn interpretar_valor(texto: &str) -> Option<f32> {
+    let texto = texto.trim().trim_start_matches("R$").trim();
+    if texto.is_empty() {
+        return None;
+    }
+    // Com vírgula, o ponto só pode ser separador de milhar.
+    let normalizado = if texto.contains(',') {
+        texto.replace('.', "").replace(',', ".")
+    } else {
+        texto.to_string()
+    };
+    normalizado.parse().ok().filter(|v: &f32| v.is_finite())
+}
+
+/// Formata o CEP como `00000-000`; textos que não têm oito dígitos são devolvidos como vieram.
+pub fn formatar_cep(cep: &str) -> String {
+    let digitos: String = cep.chars().filter(|c| c.is_ascii_digit()).collect();
+    if digitos.len() == 8 {
+        format!("{}-{}", &digitos[..5], &digitos[5..])
+    } else {
+        cep.to_string()
+    }
+}
+
+/// Prepara a discriminação do serviço para exibição: itens separados por `|` viram linhas
+/// e espaços repetidos são reduzidos a um só.
+pub fn formatar_discriminacao(texto: &str) -> String {
+    texto
+        .replace("\r\n", "\n")
+        .split(['|', '\n'])
+        .map(|linha| linha.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|linha| !linha.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Chave de ordenação de um texto: sem acentos e em minúsculas, para que "Ângela" e "angela" fiquem juntas.
+/// O texto exibido não muda; a chave serve apenas para comparar.
+pub fn chave_ordenacao(texto: &str) -> String {
+    texto.nfd().filter(|c| !is_combining_mark(*c)).flat_map(char::to_lowercase).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soma_de_10_mil_notas_fecha_no_centavo() {
+        assert_eq!(somar_valores(std::iter::repeat_n(0.1, 10_000)), 1000.0);
+        let total = somar_valores(std::iter::repeat_n(1234.56, 10_000));
+        assert_eq!((total * 100.0).round() as i64, 1_234_560_000);
+        assert_eq!(formatar_moeda(total), "R$ 12.345.600,00");
+    }
+}
@@ -0,0 +1,46 @@
+// Gráfico de faturamento por competência.
+
+use eframe::egui;
+use egui_plot::{Bar, BarChart, Plot};
+
+use crate::agrupamento::{Grupo, SEM_DATA};
+use crate::formato::formatar_moeda;
+use crate::i18n::tr;
+
+/// Desenha um gráfico de barras com o total de cada competência, em ordem cronológica.
+/// Recebe os grupos de `agrupamento::por_competencia`; notas sem data válida ficam de fora.
+pub fn faturamento_mensal(ui: &mut egui::Ui, grupos: &[Grupo]) {
+    let meses: Vec<&Grupo> = grupos.iter().filter(|g| g.chave != SEM_DATA).collect();
+    if meses.is_empty() {
+        ui.label(tr("Nenhuma nota com data de emissão válida para exibir no gráfico."));
+        return;
+    }
+
+    let barras: Vec<Bar> = meses
+        .iter()
+        .enumerate()
+        .map(|(i, grupo)| Bar::new(i as f64, grupo.total).name(&grupo.nome).width(0.7))
+        .collect();
+    let grafico = BarChart::new(barras)
+        .name(tr("Faturamento"))
+        .element_formatter(Box::new(|barra, _| format!("{}\n{}", barra.name, formatar_moeda(barra.value))));
+
+    // O eixo horizontal mostra o mês de cada barra; o vertical, valores em reais.
+    let nomes: Vec<String> = meses.iter().map(|g| g.nome.clone()).collect();
+    Plot::new("faturamento_mensal")
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .show_x(false)
+        .show_y(false)
+        .x_axis_formatter(move |marca, _| {
+            let i = marca.value.round();
+            if (marca.value - i).abs() < f64::EPSILON && i >= 0.0 {
+                nomes.get(i as usize).cloned().unwrap_or_default()
+            } else {
+                String::new()
+            }
+        })
+        .y_axis_formatter(|marca, _| formatar_moeda(marca.value))
+        .show(ui, |plot_ui| plot_ui.bar_chart(grafico));
+}
@@ -0,0 +1,330 @@
+// Tradução dos textos da interface.
+//
+// Os textos são escritos em português no código e servem de chave para as traduções; um texto sem
+// tradução aparece no original. Mensagens com valores usam `{}` no lugar de cada valor.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+/// Idiomas disponíveis para a interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Lang {
+    #[default]
+    PtBr,
+    EnUs,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::PtBr, Lang::EnUs];
+
+    /// Nome do idioma no próprio idioma, como aparece no seletor.
+    pub fn label(self) -> &'static str {
+        match self {
+            Lang::PtBr => "Português (Brasil)",
+            Lang::EnUs => "English (US)",
+        }
+    }
+}
+
+/// Idioma em uso, guardado como o índice em `Lang::ALL`.
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// Define o idioma usado por `tr` e `trf!` a partir de agora.
+pub fn set_lang(lang: Lang) {
+    let index = Lang::ALL.iter().position(|l| *l == lang).unwrap_or(0);
+    CURRENT.store(index as u8, Ordering::Relaxed);
+}
+
+fn current_lang() -> Lang {
+    Lang::ALL.get(CURRENT.load(Ordering::Relaxed) as usize).copied().unwrap_or_default()
+}
+
+/// Traduções para o inglês, indexadas pelo texto original.
+const EN_US: &[(&str, &str)] = &[
+    // Menus
+    ("Arquivo", "File"),
+    ("Selecionar arquivos XML…", "Select XML files…"),
+    ("Selecionar pasta…", "Select folder…"),
+    ("Comparar lotes…", "Compare batches…"),
+    ("Abrir sessão…", "Open session…"),
+    ("Salvar sessão…", "Save session…"),
+    ("Acrescentar ao lote atual", "Add to current batch"),
+    ("Novos arquivos e pastas selecionados são somados às notas já carregadas", "Newly selected files and folders are added to the invoices already loaded"),
+    ("Incluir subpastas", "Include subfolders"),
+    ("Desligado, apenas os arquivos do primeiro nível da pasta são importados", "When off, only files at the top level of the folder are imported"),
+    ("Limpar tudo", "Clear all"),
+    ("Recarregar", "Reload"),
+    ("Relê todos os arquivos do disco, mesmo os que não mudaram", "Rereads every file from disk, even unchanged ones"),
+    ("Permitir duplicatas", "Allow duplicates"),
+    ("Reabrir último lote ao iniciar", "Reopen last batch on startup"),
+    ("Tolerância da soma (R$):", "Sum tolerance (R$):"),
+    ("Valor atípico acima de (desvios padrão):", "Outlier amount above (standard deviations):"),
+    ("Notas com valor acima da média do lote mais este número de desvios padrão são destacadas em amarelo", "Invoices above the batch average plus this many standard deviations are highlighted in yellow"),
+    ("Diferença aceita entre o valor dos serviços e a soma do valor líquido com o ISS", "Accepted difference between the service amount and the sum of the net amount and the ISS"),
+    ("Sair", "Quit"),
+    ("Exportar", "Export"),
+    ("CSV…", "CSV…"),
+    ("Planilha XLSX…", "XLSX spreadsheet…"),
+    ("Escolher colunas…", "Choose columns…"),
+    ("JSON…", "JSON…"),
+    ("Relatório PDF…", "PDF report…"),
+    ("TXT posicional (contabilidade)…", "Fixed-width TXT (accounting)…"),
+    ("Dividir em arquivos XML…", "Split into XML files…"),
+    ("Grava cada nota num arquivo XML próprio, nomeado pelo número da nota", "Saves each invoice in its own XML file, named after the invoice number"),
+    ("Resumo por prestador (CSV)…", "Summary by provider (CSV)…"),
+    ("Resumo por item de serviço (CSV)…", "Summary by service item (CSV)…"),
+    ("Lista de erros (CSV)…", "Error list (CSV)…"),
+    ("Arquivos com erro, sem notas ou ignorados, para enviar a quem gerou os XMLs", "Files with errors, without invoices or ignored, to send to whoever generated the XMLs"),
+    ("Exibir", "View"),
+    ("☀ Usar tema claro", "☀ Use light theme"),
+    ("🌙 Usar tema escuro", "🌙 Use dark theme"),
+    ("Resetar layout", "Reset layout"),
+    ("Resetar ordem das colunas", "Reset column order"),
+    ("Volta a janela e os painéis ao tamanho padrão", "Restores the window and panels to their default size"),
+    ("Idioma", "Language"),
+    ("Ajuda", "Help"),
+    ("Abrir log", "Open log"),
+    ("Sobre", "About"),
+    ("Processador de Notas Fiscais", "Invoice Processor"),
+    ("Versão {}", "Version {}"),
+    ("Visualizador de XML de Nota Fiscal de Serviço Eletrônica (NFS-e) da Nota Carioca.", "Viewer for Nota Carioca electronic service invoice (NFS-e) XML files."),
+    ("Código-fonte", "Source code"),
+    // Exportação
+    ("Exportar:", "Export:"),
+    ("notas filtradas", "filtered invoices"),
+    ("todas as notas", "all invoices"),
+    ("Exportando {} de {} notas", "Exporting {} of {} invoices"),
+    ("Exportar resumo CSV", "Export CSV summary"),
+    ("Notas exportadas para {}", "Invoices exported to {}"),
+    ("Lista de erros exportada para {}", "Error list exported to {}"),
+    ("Sobrescrever arquivos", "Overwrite files"),
+    ("{} arquivo(s) já existe(m) na pasta escolhida. Deseja sobrescrevê-lo(s)?", "{} file(s) already exist in the chosen folder. Do you want to overwrite them?"),
+    ("{} nota(s) gravada(s) em {}", "{} invoice(s) saved to {}"),
+    ("; {} nota(s) sem o XML original ficaram de fora", "; {} invoice(s) without the original XML were left out"),
+    // Seleção e processamento
+    ("⏸ Monitoramento pausado:", "⏸ Monitoring paused:"),
+    ("● Monitorando:", "● Monitoring:"),
+    ("Retomar", "Resume"),
+    ("Pausar", "Pause"),
+    ("Parar", "Stop"),
+    ("Monitorar pasta", "Watch folder"),
+    ("Importa automaticamente os XMLs que chegarem na pasta selecionada", "Automatically imports XML files that arrive in the selected folder"),
+    ("{} arquivo(s) importado(s) da pasta monitorada", "{} file(s) imported from the watched folder"),
+    ("Procurando XMLs... {} arquivo(s) encontrado(s)", "Searching for XML files... {} file(s) found"),
+    ("Processando: {} de {} arquivos", "Processing: {} of {} files"),
+    ("Recarregado: {} arquivo(s) relido(s) do disco", "Reloaded: {} file(s) reread from disk"),
+    ("Arquivos Selecionados:", "Selected Files:"),
+    ("Nenhum arquivo selecionado.", "No files selected."),
+    ("Nenhum arquivo XML encontrado nesta pasta.", "No XML files found in this folder."),
+    ("Nenhum dos arquivos soltos é um XML ou ZIP.", "None of the dropped files is an XML or ZIP file."),
+    ("{} arquivo(s) encontrado(s), mas nenhuma nota pôde ser lida. Veja os detalhes abaixo.", "{} file(s) found, but no invoice could be read. See the details below."),
+    ("— {} nota(s)", "— {} invoice(s)"),
+    ("— ignorado (não é NFS-e)", "— ignored (not an NFS-e)"),
+    ("Remover da seleção", "Remove from selection"),
+    ("Arquivo modificado no disco desde o processamento; clique para recarregá-lo", "File modified on disk since processing; click to reload it"),
+    ("Arquivo recarregado: {}", "File reloaded: {}"),
+    ("{} arquivo(s) com erro", "{} file(s) with errors"),
+    ("{} arquivo(s) sem notas", "{} file(s) without invoices"),
+    ("Estes arquivos foram lidos, mas não contêm nenhuma nota (CompNfse):", "These files were read but contain no invoices (CompNfse):"),
+    ("{} arquivo(s) ignorado(s)", "{} file(s) ignored"),
+    ("Estes XMLs não são notas fiscais de serviço e foram ignorados:", "These XML files are not service invoices and were ignored:"),
+    ("Solte os arquivos aqui", "Drop files here"),
+    ("Processamento em andamento", "Processing in progress"),
+    ("Ainda há arquivos sendo processados. Se sair agora, esse trabalho será perdido.", "Files are still being processed. If you quit now, that work will be lost."),
+    ("O processamento terminou.", "Processing has finished."),
+    ("Deseja sair?", "Do you want to quit?"),
+    ("Cancelar", "Cancel"),
+    ("Sessão salva em {}", "Session saved to {}"),
+    ("Sessão aberta: {}", "Session opened: {}"),
+    ("{} arquivo(s) da sessão não existe(m) mais e ficou(aram) de fora: {}", "{} session file(s) no longer exist and were left out: {}"),
+    // Comparação de lotes
+    ("Comparar lotes", "Compare batches"),
+    ("Lote A:", "Batch A:"),
+    ("Lote B:", "Batch B:"),
+    ("Escolher pasta…", "Choose folder…"),
+    ("nenhuma pasta escolhida", "no folder chosen"),
+    ("Comparar", "Compare"),
+    ("Lendo as notas dos dois lotes...", "Reading the invoices of both batches..."),
+    ("Os dois lotes têm as mesmas notas.", "Both batches have the same invoices."),
+    ("Só no lote A: {}", "Only in batch A: {}"),
+    ("Só no lote B: {}", "Only in batch B: {}"),
+    ("Exportar CSV", "Export CSV"),
+    ("Situação", "Status"),
+    ("Só no lote A", "Only in batch A"),
+    ("Só no lote B", "Only in batch B"),
+    // Filtros
+    ("Buscar:", "Search:"),
+    ("prestador, tomador ou número da nota", "provider, customer or invoice number"),
+    ("Incluir canceladas", "Include canceled"),
+    ("Filtrar por data", "Filter by date"),
+    ("De:", "From:"),
+    ("Até:", "To:"),
+    ("Incluir datas inválidas", "Include invalid dates"),
+    ("Valor entre:", "Amount between:"),
+    ("mínimo", "minimum"),
+    ("sem limite", "no limit"),
+    ("Valor inválido; o limite será ignorado", "Invalid amount; the limit will be ignored"),
+    ("Prestador:", "Provider:"),
+    ("Todos", "All"),
+    ("Competências", "Billing months"),
+    ("Mostrar todas", "Show all"),
+    ("⚠ Lacunas na numeração: {} nota(s) faltando", "⚠ Numbering gaps: {} invoice(s) missing"),
+    ("Mostrando a competência predominante ({}, {} de {} notas); limpe o filtro de datas para ver tudo.", "Showing the predominant billing month ({}, {} of {} invoices); clear the date filter to see everything."),
+    // Resumo
+    ("{} resultado(s) para \"{}\"", "{} result(s) for \"{}\""),
+    ("Notas Fiscais Processadas: {}", "Processed Invoices: {}"),
+    ("Notas Fiscais Processadas: {} (de {})", "Processed Invoices: {} (of {})"),
+    ("Quantidade: {}", "Count: {}"),
+    ("Total: {}", "Total: {}"),
+    ("Média: {}", "Average: {}"),
+    ("Total líquido: {}", "Net total: {}"),
+    ("Total ISS: {}", "ISS total: {}"),
+    ("({} sem ISS)", "({} without ISS)"),
+    ("Notas que não informam o valor do ISS não entram no total de ISS", "Invoices that do not state the ISS amount are left out of the ISS total"),
+    ("{} nota(s) sem ISS informado não entram no total líquido", "{} invoice(s) without ISS are left out of the net total"),
+    ("Canceladas ignoradas: {}", "Canceled ignored: {}"),
+    ("Arquivos sem notas: {}", "Files without invoices: {}"),
+    ("Duplicadas ignoradas: {}", "Duplicates ignored: {}"),
+    ("Documentos inválidos: {}", "Invalid documents: {}"),
+    ("Somas inconsistentes: {}", "Inconsistent sums: {}"),
+    ("Notas em que o valor dos serviços difere da soma do valor líquido com o ISS", "Invoices whose service amount differs from the sum of the net amount and the ISS"),
+    ("Tomadores:", "Customers:"),
+    ("Processado em {} ms ({} arquivos/s)", "Processed in {} ms ({} files/s)"),
+    ("Sem documento", "No document"),
+    // Visualizações
+    ("Visualização:", "View:"),
+    ("Lista plana", "Flat list"),
+    ("Agrupado por prestador", "Grouped by provider"),
+    ("Agrupado por item de serviço", "Grouped by service item"),
+    ("Agrupado por tomador", "Grouped by customer"),
+    ("Gráfico por mês", "Chart by month"),
+    ("Nenhuma nota com data de emissão válida para exibir no gráfico.", "No invoice with a valid issue date to show in the chart."),
+    ("Faturamento", "Revenue"),
+    ("{}: {} nota(s), {}", "{}: {} invoice(s), {}"),
+    ("{} — {} nota(s) — {}", "{} — {} invoice(s) — {}"),
+    ("Nº {}  ·  {}  ·  {}  ·  {}", "No. {}  ·  {}  ·  {}  ·  {}"),
+    // Tabela
+    ("Número", "Number"),
+    ("Data", "Date"),
+    ("Prestador", "Provider"),
+    ("CNPJ Prestador", "Provider CNPJ"),
+    ("Tomador", "Customer"),
+    ("CPF/CNPJ Tomador", "Customer CPF/CNPJ"),
+    ("Valor", "Amount"),
+    ("Valor Líquido", "Net Amount"),
+    ("Nota cancelada", "Canceled invoice"),
+    ("Data inválida", "Invalid date"),
+    ("Data de Emissão", "Issue Date"),
+    ("Valor dos Serviços", "Service Amount"),
+    ("Valor do ISS", "ISS Amount"),
+    ("Discriminação", "Service Description"),
+    ("Anotação", "Note"),
+    ("Exportar com colunas escolhidas", "Export with chosen columns"),
+    ("Marque os campos a incluir; a ordem das colunas segue a ordem de marcação.", "Check the fields to include; columns follow the order in which they were checked."),
+    ("Nenhuma coluna marcada.", "No column checked."),
+    ("Ordem: {}", "Order: {}"),
+    ("Restaurar padrão", "Restore default"),
+    ("Dia da Semana", "Weekday"),
+    ("Competência", "Billing month"),
+    ("Segunda-feira", "Monday"),
+    ("Terça-feira", "Tuesday"),
+    ("Quarta-feira", "Wednesday"),
+    ("Quinta-feira", "Thursday"),
+    ("Sexta-feira", "Friday"),
+    ("Sábado", "Saturday"),
+    ("Domingo", "Sunday"),
+    ("Dígito verificador inválido", "Invalid check digit"),
+    ("Tomador no exterior", "Customer abroad"),
+    ("A nota não informa o ISS", "The invoice does not state the ISS"),
+    ("Valor dos serviços difere da soma do valor líquido com o ISS", "Service amount differs from the sum of the net amount and the ISS"),
+    ("Valor dos serviços ausente no XML", "Service amount missing in the XML"),
+    ("Valor muito acima da média do lote; confira se não houve erro de digitação", "Amount far above the batch average; check for a typing error"),
+    ("Selecionar todas/nenhuma", "Select all/none"),
+    ("Copiar selecionadas ({})", "Copy selected ({})"),
+    ("{} nota(s) copiada(s) para a área de transferência", "{} invoice(s) copied to the clipboard"),
+    ("Copiar tabela (TSV)", "Copy table (TSV)"),
+    ("Copia todas as notas exibidas, com as colunas escolhidas em \"Exportar › Escolher colunas…\", para colar numa planilha", "Copies every invoice shown, with the columns chosen in \"Export › Choose columns…\", to paste into a spreadsheet"),
+    ("Copia as notas marcadas como tabela separada por tabulação, pronta para colar numa planilha", "Copies the checked invoices as a tab-separated table, ready to paste into a spreadsheet"),
+    // Detalhes da nota
+    ("Nota {}", "Invoice {}"),
+    ("Copiado!", "Copied!"),
+    ("Copiar", "Copy"),
+    ("Arquivo: {}", "File: {}"),
+    ("Abrir pasta do arquivo", "Open file folder"),
+    ("Abrir XML", "Open XML"),
+    ("Abre o arquivo no programa padrão do sistema", "Opens the file in the system's default program"),
+    ("⊘ Nota cancelada", "⊘ Canceled invoice"),
+    ("Identificador: {}…", "Identifier: {}…"),
+    ("Número: {}", "Number: {}"),
+    ("Código de Verificação: {}", "Verification Code: {}"),
+    ("Verificar no portal", "Verify on the portal"),
+    ("Data de Emissão: {}", "Issue Date: {}"),
+    ("Data de Emissão: {} (data inválida)", "Issue Date: {} (invalid date)"),
+    ("Prestador: {}", "Provider: {}"),
+    ("CNPJ Prestador: {}", "Provider CNPJ: {}"),
+    ("Endereço do prestador", "Provider address"),
+    ("Tomador: {}", "Customer: {}"),
+    ("Tomador:", "Customer:"),
+    ("(tomador sem nome)", "(unnamed customer)"),
+    ("CNPJ Tomador: {}", "Customer CNPJ: {}"),
+    ("CPF Tomador: {}", "Customer CPF: {}"),
+    ("Documento no exterior (NIF): {}", "Foreign document (NIF): {}"),
+    ("🌐 Tomador no exterior", "🌐 Customer abroad"),
+    ("⚠ Documento do tomador com dígito verificador inválido", "⚠ Customer document with invalid check digit"),
+    ("Endereço do tomador", "Customer address"),
+    ("Valor: {}", "Amount: {}"),
+    ("Item da Lista de Serviço: {}", "Service List Item: {}"),
+    ("Código de Tributação Municipal: {}", "Municipal Tax Code: {}"),
+    ("Base de Cálculo: {}", "Tax Base: {}"),
+    ("Alíquota: {}", "Tax Rate: {}"),
+    ("Valor do ISS: {}", "ISS Amount: {}"),
+    ("Valor Líquido: {}", "Net Amount: {}"),
+    ("⚠ Valor líquido declarado ({}) mais ISS não soma o valor dos serviços", "⚠ Declared net amount ({}) plus ISS does not add up to the service amount"),
+    ("⚠ Valor dos serviços ausente no XML", "⚠ Service amount missing in the XML"),
+    ("Descrição:", "Description:"),
+    ("Copiar linha completa (separada por tabulação):", "Copy full row (tab separated):"),
+    ("Gerar espelho PDF…", "Generate PDF copy…"),
+    ("Espelho da nota salvo em {}", "Invoice copy saved to {}"),
+    ("PDF de uma página com todos os dados da nota, para imprimir ou anexar", "One-page PDF with all the invoice data, to print or attach"),
+    ("ver XML", "view XML"),
+    ("Anotação:", "Note:"),
+    ("conferida, pendência…", "checked, pending…"),
+];
+
+fn en_us() -> &'static HashMap<&'static str, &'static str> {
+    static TABLE: OnceLock<HashMap<&'static str, &'static str>> = OnceLock::new();
+    TABLE.get_or_init(|| EN_US.iter().copied().collect())
+}
+
+/// Traduz um texto da interface para o idioma em uso.
+pub fn tr(texto: &'static str) -> &'static str {
+    match current_lang() {
+        Lang::PtBr => texto,
+        Lang::EnUs => en_us().get(texto).copied().unwrap_or(texto),
+    }
+}
+
+/// Substitui, em ordem, cada `{}` do modelo pelo valor correspondente.
+pub fn fill(modelo: &str, valores: &[&dyn Display]) -> String {
+    let mut partes = modelo.split("{}");
+    let mut texto = partes.next().unwrap_or_default().to_string();
+    for (i, parte) in partes.enumerate() {
+        if let Some(valor) = valores.get(i) {
+            texto.push_str(&valor.to_string());
+        }
+        texto.push_str(parte);
+    }
+    texto
+}
+
+/// Como `format!`, mas traduzindo o modelo antes de preencher os valores.
+macro_rules! trf {
+    ($modelo:literal $(, $valor:expr)* $(,)?) => {
+        $crate::i18n::fill($crate::i18n::tr($modelo), &[$(&$valor as &dyn std::fmt::Display),*])
+    };
+}
+pub(crate) use trf;
@@ -1,14 +1,47 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use eframe::{egui, run_native, NativeOptions};
 use tinyfiledialogs as tfd;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 use std::fs;
-use std::io::Read;
-use chrono::{NaiveDate, Datelike};
+use std::io::{BufRead, BufReader, Read};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use chrono::{Datelike, Local, Months, NaiveDate, Weekday};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use egui_extras::{Column, DatePickerButton, TableBuilder};
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
 use walkdir::WalkDir;
 
+mod agrupamento;
+mod cli;
+mod comparacao;
+mod diagnostico;
+mod documento;
+mod export;
+mod formato;
+mod grafico;
+mod i18n;
+mod monitor;
+mod pdf;
+mod registro;
+mod sessao;
+
+use documento::formatar_documento;
+use formato::{
+    centavos, chave_ordenacao, formatar_aliquota, formatar_cep, formatar_discriminacao, formatar_moeda, interpretar_valor,
+    media_desvio, somar_valores,
+};
+use i18n::{tr, trf, Lang};
+
 // Define as estruturas de dados para desserializar o XML da nota fiscal.
-// Cada struct corresponde a um elemento no XML.
+// Cada struct corresponde a um elemento no XML. O desserializador do quick-xml compara apenas o nome
+// local das tags, então XMLs com prefixo de namespace (`ns:CompNfse`, `nfse:Nfse`) são lidos como os
+// sem prefixo; as buscas feitas com `quick_xml::Reader` também usam `local_name` pelo mesmo motivo.
 
 /// Representa a resposta da consulta de NFSe.
 #[derive(Debug, Deserialize, Clone)]
@@ -30,6 +63,18 @@ struct ListaNfse {
 struct CompNfse {
     #[serde(rename = "Nfse")]
     nfse: Nfse,
+    /// Presente quando a nota foi cancelada; o conteúdo (confirmação, data) não é usado.
+    #[serde(rename = "NfseCancelamento", default)]
+    nfse_cancelamento: Option<serde::de::IgnoredAny>,
+}
+
+impl CompNfse {
+    /// Extrai os dados da nota, marcando-a como cancelada se houver o bloco de cancelamento.
+    fn into_inf_nfse(self) -> InfNfse {
+        let mut inf = self.nfse.inf_nfse;
+        inf.cancelada = self.nfse_cancelamento.is_some();
+        inf
+    }
 }
 
 /// Contém as informações da nota fiscal.
@@ -40,221 +85,4146 @@ struct Nfse {
 }
 
 /// Detalhes da nota fiscal.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 struct InfNfse {
     numero: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    codigo_verificacao: Option<String>,
     data_emissao: String,
     servico: Servico,
     prestador_servico: Prestador,
     tomador_servico: Tomador,
+    /// Preenchido a partir do `NfseCancelamento` de `CompNfse`; não é um campo de `InfNfse`.
+    #[serde(skip_deserializing, skip_serializing_if = "std::ops::Not::not")]
+    cancelada: bool,
+    /// Trecho original do XML de onde a nota foi lida (o `CompNfse`), para conferência.
+    /// Fica vazio nas notas de arquivos lidos em streaming, para não manter o arquivo inteiro em memória.
+    #[serde(skip)]
+    xml: String,
 }
 
 /// Informações sobre o serviço prestado.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 struct Servico {
     valores: Valores,
+    /// Código do item da lista de serviços (LC 116/2003), por exemplo `0107`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    item_lista_servico: Option<String>,
+    /// Código de tributação do serviço no município.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    codigo_tributacao_municipio: Option<String>,
     discriminacao: String,
 }
 
 /// Valores relacionados ao serviço.
-#[derive(Debug, Deserialize, Clone)]
-#[serde(rename_all = "PascalCase")]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "PascalCase", from = "ValoresXml")]
 struct Valores {
     valor_servicos: f32,
+    /// O XML traz `<ValorServicos>` vazio; a nota entra com valor zero, mas marcada.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    valor_servicos_ausente: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    valor_iss: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aliquota: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base_calculo: Option<f32>,
+    /// Valor líquido declarado no XML; o exibido nas telas é calculado por `valor_liquido`.
+    #[serde(rename = "ValorLiquidoNfse", skip_serializing_if = "Option::is_none")]
+    valor_liquido_nfse: Option<f32>,
+}
+
+/// `Valores` como vêm no XML, com o valor dos serviços possivelmente vazio.
+/// A tag continua obrigatória: só o conteúdo vazio é aceito.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ValoresXml {
+    #[serde(deserialize_with = "deserialize_decimal_opcional")]
+    valor_servicos: Option<f32>,
+    #[serde(default, deserialize_with = "deserialize_decimal_opcional")]
+    valor_iss: Option<f32>,
+    #[serde(default, deserialize_with = "deserialize_decimal_opcional")]
+    aliquota: Option<f32>,
+    #[serde(default, deserialize_with = "deserialize_decimal_opcional")]
+    base_calculo: Option<f32>,
+    #[serde(rename = "ValorLiquidoNfse", default, deserialize_with = "deserialize_decimal_opcional")]
+    valor_liquido_nfse: Option<f32>,
+}
+
+impl From<ValoresXml> for Valores {
+    fn from(xml: ValoresXml) -> Self {
+        Self {
+            valor_servicos: xml.valor_servicos.unwrap_or(0.0),
+            valor_servicos_ausente: xml.valor_servicos.is_none(),
+            valor_iss: xml.valor_iss,
+            aliquota: xml.aliquota,
+            base_calculo: xml.base_calculo,
+            valor_liquido_nfse: xml.valor_liquido_nfse,
+        }
+    }
+}
+
+impl Valores {
+    /// Valor dos serviços descontado o ISS; `None` quando a nota não informa o ISS.
+    fn valor_liquido(&self) -> Option<f32> {
+        self.valor_iss.map(|iss| self.valor_servicos - iss)
+    }
+
+    /// Confere se o valor dos serviços é a soma do líquido declarado com o ISS, dentro da tolerância.
+    /// `None` quando a nota não informa os três valores.
+    fn soma_consistente(&self, tolerancia: f32) -> Option<bool> {
+        let (liquido, iss) = (self.valor_liquido_nfse?, self.valor_iss?);
+        // A comparação é feita em centavos para que o arredondamento do `f32` não gere falsos alertas.
+        let diferenca = centavos(self.valor_servicos) - centavos(liquido) - centavos(iss);
+        Some(diferenca.abs() <= centavos(tolerancia))
+    }
+}
+
+/// Lê um valor numérico aceitando tanto número quanto texto; no texto, ponto (`1234.56`) e vírgula (`1234,56`)
+/// servem de separador decimal, e aspas em volta do valor (`"1234.56"`), usadas por alguns emissores, são ignoradas.
+/// Um elemento vazio equivale a um campo ausente.
+fn deserialize_decimal_opcional<'de, D>(deserializer: D) -> Result<Option<f32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    deserializer.deserialize_option(DecimalVisitor)
+}
+
+/// Visitante de `deserialize_decimal_opcional`.
+struct DecimalVisitor;
+
+impl<'de> serde::de::Visitor<'de> for DecimalVisitor {
+    type Value = Option<f32>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("um valor numérico")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, texto: &str) -> Result<Self::Value, E> {
+        let valor = texto.trim().trim_matches(['"', '\'']);
+        if valor.trim().is_empty() {
+            return Ok(None);
+        }
+        interpretar_valor(valor)
+            .map(Some)
+            .ok_or_else(|| E::custom(format!("valor numérico inválido: \"{}\"", texto)))
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, numero: f64) -> Result<Self::Value, E> {
+        Ok(Some(numero as f32))
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, numero: i64) -> Result<Self::Value, E> {
+        Ok(Some(numero as f32))
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, numero: u64) -> Result<Self::Value, E> {
+        Ok(Some(numero as f32))
+    }
+
+    fn visit_none<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: serde::de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    // No XML o conteúdo do elemento é sempre texto; pedir `str` evita que o elemento seja lido como mapa.
+    fn visit_some<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_str(self)
+    }
 }
 
 /// Dados do prestador de serviço.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 struct Prestador {
     razao_social: String,
     identificacao_prestador: IdentificacaoPrestador,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    endereco: Option<Endereco>,
 }
 
 /// Identificação do prestador (CNPJ).
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 struct IdentificacaoPrestador {
     cnpj: String,
 }
 
 /// Dados do tomador de serviço.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 struct Tomador {
-    razao_social: String,
+    /// Algumas notas trazem só o documento do tomador, sem a razão social; use `nome` para ler.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    razao_social: Option<String>,
     identificacao_tomador: IdentificacaoTomador,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    endereco: Option<Endereco>,
 }
 
-/// Identificação do tomador (CPF ou CNPJ).
-#[derive(Debug, Deserialize, Clone)]
+impl Tomador {
+    /// Razão social do tomador; `None` quando o XML não a informa ou a traz vazia.
+    fn nome(&self) -> Option<&str> {
+        self.razao_social.as_deref().filter(|nome| !nome.trim().is_empty())
+    }
+}
+
+/// Identificação do tomador: CPF ou CNPJ ou, para tomadores no exterior, o documento estrangeiro (NIF).
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 struct IdentificacaoTomador {
-    #[serde(rename = "CpfCnpj")]
+    #[serde(rename = "CpfCnpj", default)]
     cpf_cnpj: CpfCnpj,
+    #[serde(rename = "NifTomador", alias = "Nif", default, skip_serializing_if = "Option::is_none")]
+    nif_estrangeiro: Option<String>,
+}
+
+impl IdentificacaoTomador {
+    /// Indica se o tomador está no exterior: sem CPF nem CNPJ, mas com o documento estrangeiro.
+    fn no_exterior(&self) -> bool {
+        self.cpf_cnpj.documento().is_none() && self.nif_estrangeiro.is_some()
+    }
+
+    /// Retorna o CNPJ ou o CPF; na falta de ambos, o documento estrangeiro.
+    fn documento(&self) -> Option<&str> {
+        self.cpf_cnpj.documento().or(self.nif_estrangeiro.as_deref())
+    }
+}
+
+/// Endereço do prestador ou do tomador. Todos os campos são opcionais, pois muitos emissores os omitem.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+struct Endereco {
+    #[serde(rename = "Endereco", default, skip_serializing_if = "Option::is_none")]
+    logradouro: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    numero: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    complemento: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bairro: Option<String>,
+    /// Código IBGE do município; o XML não traz o nome da cidade.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    codigo_municipio: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    uf: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cep: Option<String>,
 }
 
 /// Estrutura para armazenar CPF ou CNPJ.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 struct CpfCnpj {
-    #[serde(rename = "Cnpj")]
+    #[serde(rename = "Cnpj", skip_serializing_if = "Option::is_none")]
     cnpj: Option<String>,
-    #[serde(rename = "Cpf")]
+    #[serde(rename = "Cpf", skip_serializing_if = "Option::is_none")]
     cpf: Option<String>,
 }
 
+/// Nota fiscal pronta para exibição, com os campos já convertidos.
+#[derive(Debug, Clone)]
+struct ParsedInvoice {
+    inf: InfNfse,
+    /// Arquivo XML de onde a nota foi lida.
+    source: PathBuf,
+    /// Data de emissão convertida; `None` quando o texto do XML não é uma data válida.
+    data_emissao: Option<NaiveDate>,
+    /// Indica se o CPF/CNPJ do tomador passou na validação dos dígitos verificadores.
+    documento_tomador_valido: bool,
+    /// Hash SHA-256 (em hexadecimal) dos dados que identificam a nota, para conferência entre lotes.
+    identificador: String,
+    /// Anotação do usuário sobre a nota (por exemplo, "conferida"); vazia quando não há.
+    anotacao: String,
+}
+
+impl ParsedInvoice {
+    /// Cria a nota de exibição a partir dos dados desserializados do XML.
+    fn new(inf: InfNfse, source: PathBuf) -> Self {
+        let data_emissao = parse_data_emissao(&inf.data_emissao);
+        let documento_tomador_valido = inf.tomador_servico.identificacao_tomador.cpf_cnpj.is_valid();
+        let identificador = identificador_nota(&inf);
+        Self { inf, source, data_emissao, documento_tomador_valido, identificador, anotacao: String::new() }
+    }
+
+    /// Data de emissão no formato `dd/mm/aaaa`, ou o texto original se não for uma data válida.
+    fn data_emissao_formatada(&self) -> String {
+        match self.data_emissao {
+            Some(data) => data.format("%d/%m/%Y").to_string(),
+            None => self.inf.data_emissao.clone(),
+        }
+    }
+
+    /// Dia da semana da emissão, por extenso, ou "—" se a data for inválida.
+    fn dia_semana(&self) -> &'static str {
+        match self.data_emissao.map(|data| data.weekday()) {
+            Some(Weekday::Mon) => "Segunda-feira",
+            Some(Weekday::Tue) => "Terça-feira",
+            Some(Weekday::Wed) => "Quarta-feira",
+            Some(Weekday::Thu) => "Quinta-feira",
+            Some(Weekday::Fri) => "Sexta-feira",
+            Some(Weekday::Sat) => "Sábado",
+            Some(Weekday::Sun) => "Domingo",
+            None => "—",
+        }
+    }
+
+    /// Competência da emissão (`mm/aaaa`), ou "—" se a data for inválida.
+    fn competencia(&self) -> String {
+        match self.data_emissao {
+            Some(data) => data.format("%m/%Y").to_string(),
+            None => "—".to_string(),
+        }
+    }
+}
+
+/// Calcula o identificador da nota a partir do conteúdo canônico: número, CNPJ do prestador,
+/// data de emissão e valor dos serviços. Notas com esses mesmos dados geram sempre o mesmo hash.
+fn identificador_nota(inf: &InfNfse) -> String {
+    let canonico = format!(
+        "{}|{}|{}|{:.2}",
+        inf.numero,
+        inf.prestador_servico.identificacao_prestador.cnpj.trim(),
+        inf.data_emissao.trim(),
+        inf.servico.valores.valor_servicos,
+    );
+    Sha256::digest(canonico.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Converte a data de emissão no formato ISO (`2023-05-10T14:30:00`) para `NaiveDate`.
+/// Apenas a parte da data é considerada; hora e fuso horário são descartados.
+fn parse_data_emissao(texto: &str) -> Option<NaiveDate> {
+    let texto = texto.trim();
+    let data = texto.get(..10).unwrap_or(texto);
+    NaiveDate::parse_from_str(data, "%Y-%m-%d").ok()
+}
+
+/// Extrai o valor do atributo `encoding` da declaração `<?xml ... ?>`, se houver.
+fn declared_encoding(bytes: &[u8]) -> Option<String> {
+    // A declaração usa apenas caracteres ASCII, então basta olhar o início do arquivo.
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(256)]);
+    let declaration = &head[head.find("<?xml")?..];
+    let declaration = &declaration[..declaration.find("?>")?];
+    let value = &declaration[declaration.find("encoding")? + "encoding".len()..];
+    let value = value.trim_start().strip_prefix('=')?.trim_start();
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &value[1..];
+    Some(value[..value.find(quote)?].to_string())
+}
+
+/// Converte o conteúdo do arquivo para texto, respeitando o encoding declarado no cabeçalho.
+/// Sem declaração, assume UTF-8 e recorre ao Latin-1 (Windows-1252) se os bytes não forem UTF-8 válido.
+fn decode_xml(bytes: &[u8]) -> String {
+    let declared = declared_encoding(bytes).and_then(|label| Encoding::for_label(label.trim().as_bytes()));
+    let encoding = match declared {
+        Some(encoding) => encoding,
+        None if std::str::from_utf8(bytes).is_ok() => UTF_8,
+        None => WINDOWS_1252,
+    };
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+impl Endereco {
+    /// Monta as linhas do endereço para exibição, omitindo as partes ausentes ou vazias.
+    fn linhas(&self) -> Vec<String> {
+        fn campo(valor: &Option<String>) -> Option<&str> {
+            valor.as_deref().map(str::trim).filter(|v| !v.is_empty())
+        }
+        fn juntar(partes: &[Option<&str>], separador: &str) -> String {
+            partes.iter().flatten().copied().collect::<Vec<_>>().join(separador)
+        }
+
+        let municipio = campo(&self.codigo_municipio).map(|c| format!("Município {}", c));
+        let linhas = [
+            juntar(&[campo(&self.logradouro), campo(&self.numero), campo(&self.complemento)], ", "),
+            juntar(&[campo(&self.bairro)], ""),
+            juntar(&[municipio.as_deref(), campo(&self.uf)], " - "),
+            campo(&self.cep).map(|cep| format!("CEP {}", formatar_cep(cep))).unwrap_or_default(),
+        ];
+        linhas.into_iter().filter(|l| !l.is_empty()).collect()
+    }
+}
+
+/// Tipo de documento com que o tomador foi identificado na nota.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum TipoDocumento {
+    Cpf,
+    Cnpj,
+    SemDocumento,
+}
+
+impl TipoDocumento {
+    const ALL: [TipoDocumento; 3] = [TipoDocumento::Cpf, TipoDocumento::Cnpj, TipoDocumento::SemDocumento];
+
+    fn label(self) -> &'static str {
+        match self {
+            TipoDocumento::Cpf => "CPF",
+            TipoDocumento::Cnpj => "CNPJ",
+            TipoDocumento::SemDocumento => tr("Sem documento"),
+        }
+    }
+}
+
+impl CpfCnpj {
+    /// Classifica o documento do tomador; com ambos informados, vale o CNPJ, como em `documento`.
+    fn tipo(&self) -> TipoDocumento {
+        match (&self.cnpj, &self.cpf) {
+            (Some(_), _) => TipoDocumento::Cnpj,
+            (None, Some(_)) => TipoDocumento::Cpf,
+            (None, None) => TipoDocumento::SemDocumento,
+        }
+    }
+
+    /// Retorna o CNPJ ou o CPF, o que estiver presente.
+    fn documento(&self) -> Option<&str> {
+        self.cnpj.as_deref().or(self.cpf.as_deref())
+    }
+
+    /// Indica se o documento informado tem dígitos verificadores válidos.
+    /// Notas sem documento não são consideradas inválidas.
+    fn is_valid(&self) -> bool {
+        match (&self.cnpj, &self.cpf) {
+            (Some(cnpj), _) => documento::validar_cnpj(cnpj),
+            (None, Some(cpf)) => documento::validar_cpf(cpf),
+            (None, None) => true,
+        }
+    }
+}
+
 /// Analisa um único arquivo XML e retorna os dados desserializados.
-fn parse_xml_from_file(file_path: &PathBuf) -> Result<ConsultarNfseResposta, String> {
+fn parse_xml_from_file(file_path: &PathBuf) -> Vec<DocumentResult> {
+    let single_error =
+        |message: String| vec![DocumentResult { source: file_path.clone(), result: Err(DocumentError::Failed(message)) }];
+
     // Abre o arquivo XML.
     let mut file = match fs::File::open(file_path) {
         Ok(file) => file,
-        Err(e) => return Err(format!("Erro ao abrir o arquivo \"{:?}\": {}", file_path, e)),
+        Err(e) => return single_error(format!("Erro ao abrir o arquivo \"{:?}\": {}", file_path, e)),
     };
 
-    // Lê o conteúdo do arquivo para uma string.
-    let mut contents = String::new();
-    if let Err(e) = file.read_to_string(&mut contents) {
-        return Err(format!("Erro ao ler o arquivo: {}", e));
+    // Arquivos grandes são lidos nota a nota, sem carregar o documento inteiro na memória.
+    if file.metadata().is_ok_and(|m| m.len() >= STREAMING_MIN_SIZE) {
+        match fs::File::open(file_path).map(|file| parse_xml_streaming(file, file_path)) {
+            Ok(Some(documents)) => return documents,
+            Ok(None) => {}
+            Err(e) => return single_error(format!("Erro ao abrir o arquivo \"{:?}\": {}", file_path, e)),
+        }
     }
 
-    // Remove o BOM (Byte Order Mark) do início do arquivo, se existir.
-    let contents = contents.trim_start_matches('\u{feff}');
+    // Lê o conteúdo bruto do arquivo.
+    let mut bytes = Vec::new();
+    if let Err(e) = file.read_to_end(&mut bytes) {
+        return single_error(format!("Erro ao ler o arquivo: {}", e));
+    }
 
-    // Desserializa o conteúdo XML para a estrutura de dados.
-    let resposta: Result<ConsultarNfseResposta, _> = quick_xml::de::from_str(contents);
+    parse_xml_document(&bytes, file_path)
+}
+
+/// Tamanho a partir do qual um arquivo XML é lido em streaming, por `parse_xml_streaming`.
+const STREAMING_MIN_SIZE: u64 = 32 * 1024 * 1024;
+
+/// Lê o arquivo como um fluxo de eventos XML, desserializando cada `CompNfse` assim que ele termina,
+/// de modo que apenas uma nota por vez fica em memória além das já lidas. O trecho de XML de cada nota
+/// não é guardado: com todos eles, a memória usada voltaria a ser a do arquivo inteiro.
+/// XMLs de outros sistemas são recusados pela mesma verificação de `raiz_nao_nfse`.
+/// Retorna `None` quando o arquivo deve ser lido por inteiro: encoding que não é compatível com ASCII
+/// (como UTF-16) ou documento NFS-e sem nenhum `CompNfse`.
+fn parse_xml_streaming(file: fs::File, file_path: &Path) -> Option<Vec<DocumentResult>> {
+    let mut reader = BufReader::new(file);
+    let declared = declared_encoding(reader.fill_buf().ok()?).and_then(|label| Encoding::for_label(label.trim().as_bytes()));
+    if declared.is_some_and(|encoding| !encoding.is_ascii_compatible()) {
+        return None;
+    }
+
+    let document = |result: Result<Vec<InfNfse>, String>| DocumentResult {
+        source: file_path.to_path_buf(),
+        result: result.map_err(DocumentError::Failed),
+    };
+    let mut reader = quick_xml::Reader::from_reader(reader);
+    let mut buf = Vec::new();
+    let mut invoices = Vec::new();
+    let mut documents = Vec::new();
+    let mut count = 0;
+    let mut raiz = RaizDocumento::default();
+    let mut malformado = false;
+    loop {
+        let fragment = match reader.read_event_into(&mut buf) {
+            Ok(quick_xml::events::Event::Start(e)) if e.local_name().as_ref() == b"CompNfse" => {
+                count += 1;
+                raiz.observar(e.local_name().as_ref());
+                read_fragment(&mut reader, e.into_owned())
+            }
+            Ok(quick_xml::events::Event::Start(e) | quick_xml::events::Event::Empty(e)) => {
+                raiz.observar(e.local_name().as_ref());
+                buf.clear();
+                continue;
+            }
+            Ok(quick_xml::events::Event::Eof) => break,
+            Ok(_) => {
+                buf.clear();
+                continue;
+            }
+            Err(e) => Err(e),
+        };
+        buf.clear();
+        let fragment = match fragment {
+            Ok(fragment) => fragment,
+            // Sem como saber onde a próxima nota começa, a leitura para no primeiro erro de sintaxe.
+            Err(e) => {
+                documents.push(document(Err(format!(
+                    "Erro ao processar o XML em \"{:?}\" depois da nota {}: XML malformado: {}",
+                    file_path,
+                    invoices.len(),
+                    e
+                ))));
+                malformado = true;
+                break;
+            }
+        };
+        // Sem declaração de encoding, cada nota é decodificada como em `decode_xml`.
+        let text = match declared {
+            Some(encoding) => encoding.decode(&fragment).0.into_owned(),
+            None => decode_xml(&fragment),
+        };
+        match quick_xml::de::from_str::<CompNfse>(&text) {
+            Ok(comp_nfse) => invoices.push(comp_nfse.into_inf_nfse()),
+            Err(e) => documents.push(document(Err(format!(
+                "Erro ao processar a nota {} em \"{:?}\": {}",
+                count,
+                file_path,
+                diagnostico::descrever_erro(&e, &text, 0)
+            )))),
+        }
+    }
 
-    // Retorna o resultado da desserialização.
-    match resposta {
-        Ok(r) => Ok(r),
-        Err(e) => Err(format!("Erro ao processar o XML em \"{:?}\": {}", file_path, e)),
+    // Como em `raiz_nao_nfse`, XML malformado não é recusado aqui.
+    if let Some(raiz) = raiz.nao_nfse().filter(|_| !malformado) {
+        return Some(vec![documento_nao_nfse(file_path, &raiz)]);
+    }
+    if count == 0 {
+        return None;
+    }
+    if !invoices.is_empty() {
+        documents.insert(0, document(Ok(invoices)));
     }
+    Some(documents)
 }
 
-/// Estrutura principal da aplicação de GUI.
-struct TemplateApp {
-    selected_files: Vec<PathBuf>,
-    parsed_invoices: Vec<InfNfse>,
-    error_message: Option<String>,
+/// Copia os bytes de um elemento, da tag de abertura `start` (já lida) até a de fechamento correspondente.
+fn read_fragment<R: BufRead>(
+    reader: &mut quick_xml::Reader<R>,
+    start: quick_xml::events::BytesStart<'static>,
+) -> Result<Vec<u8>, quick_xml::Error> {
+    let mut writer = quick_xml::Writer::new(Vec::new());
+    writer.write_event(quick_xml::events::Event::Start(start))?;
+    let mut buf = Vec::new();
+    let mut depth = 1;
+    while depth > 0 {
+        let event = reader.read_event_into(&mut buf)?;
+        match &event {
+            quick_xml::events::Event::Start(_) => depth += 1,
+            quick_xml::events::Event::End(_) => depth -= 1,
+            quick_xml::events::Event::Eof => return Err(quick_xml::Error::UnexpectedEof("CompNfse".to_string())),
+            _ => {}
+        }
+        writer.write_event(event)?;
+        buf.clear();
+    }
+    Ok(writer.into_inner())
 }
 
-impl Default for TemplateApp {
-    /// Cria uma nova instância da aplicação.
-    fn default() -> Self {
-        Self {
-            selected_files: Vec::new(),
-            parsed_invoices: Vec::new(),
-            error_message: None,
+/// Desserializa o documento nota a nota: cada `CompNfse` é lido separadamente, de modo que uma nota
+/// com problema não descarta as demais do mesmo arquivo. As notas válidas vêm num único resultado e
+/// cada nota com falha gera um resultado de erro indicando sua posição no arquivo.
+fn parse_xml_document(bytes: &[u8], file_path: &Path) -> Vec<DocumentResult> {
+    let contents = decode_xml(bytes);
+    let contents = contents.trim_start_matches('\u{feff}');
+    let document = |result: Result<Vec<InfNfse>, String>| DocumentResult {
+        source: file_path.to_path_buf(),
+        result: result.map_err(DocumentError::Failed),
+    };
+
+    // XMLs de outros sistemas são reconhecidos antes da desserialização, que daria um erro confuso.
+    if let Some(raiz) = raiz_nao_nfse(contents) {
+        return vec![documento_nao_nfse(file_path, &raiz)];
+    }
+
+    // Sem `CompNfse` (ou com XML malformado), o documento é lido de uma vez, como antes.
+    let fragments = split_comp_nfse(contents).unwrap_or_default();
+    if fragments.len() <= 1 {
+        let result = parse_xml_bytes(bytes, file_path).map(|mut invoices| {
+            // Uma nota sem `CompNfse` (um `Nfse` isolado) ocupa o documento inteiro.
+            let fragment = fragments.first().map_or(contents, |(_, fragment)| fragment);
+            if let [invoice] = invoices.as_mut_slice() {
+                invoice.xml = fragment.to_string();
+            }
+            invoices
+        });
+        return vec![document(result)];
+    }
+
+    let total = fragments.len();
+    let mut invoices = Vec::new();
+    let mut documents = Vec::new();
+    for (i, (inicio, fragment)) in fragments.into_iter().enumerate() {
+        match quick_xml::de::from_str::<CompNfse>(fragment) {
+            Ok(comp_nfse) => {
+                let mut invoice = comp_nfse.into_inf_nfse();
+                invoice.xml = fragment.to_string();
+                invoices.push(invoice);
+            }
+            Err(e) => documents.push(document(Err(format!(
+                "Erro ao processar a nota {} de {} em \"{:?}\": {}",
+                i + 1,
+                total,
+                file_path,
+                diagnostico::descrever_erro(&e, contents, inicio)
+            )))),
         }
     }
+    if !invoices.is_empty() {
+        documents.insert(0, document(Ok(invoices)));
+    }
+    documents
 }
 
-impl eframe::App for TemplateApp {
-    /// Atualiza a interface gráfica a cada frame.
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("Processador de Notas Fiscais");
+/// Elementos raiz aceitos como NFS-e.
+const RAIZES_NFSE: [&str; 3] = ["ConsultarNfseResposta", "CompNfse", "Nfse"];
 
-            // Botões para selecionar arquivos ou pastas.
-            ui.horizontal(|ui| {
-                // Botão para selecionar múltiplos arquivos XML.
-                if ui.button("Selecionar Arquivos XML").clicked() {
-                    let files = tfd::open_file_dialog_multi("Selecione os arquivos XML", "", Some((&["*.xml"], "Arquivos XML")));
-                    if let Some(files) = files {
-                        self.selected_files = files.into_iter().map(PathBuf::from).collect();
-                        self.process_files();
-                    }
-                }
-                // Botão para selecionar uma pasta.
-                if ui.button("Selecionar Pasta").clicked() {
-                    let folder = tfd::select_folder_dialog("Selecione uma pasta", "");
-                    if let Some(folder) = folder {
-                        // Percorre a pasta e subpastas em busca de arquivos XML.
-                        self.selected_files = WalkDir::new(folder)
-                            .into_iter()
-                            .filter_map(|e| e.ok())
-                            .filter(|e| e.path().extension().and_then(|s| s.to_str()) == Some("xml"))
-                            .map(|e| e.path().to_path_buf())
-                            .collect();
-                        self.process_files();
-                    }
+/// Verifica se o documento é uma NFS-e e, se não for, devolve o nome do elemento raiz.
+/// Além das raízes de `RAIZES_NFSE`, aceita outras respostas do webservice que contenham
+/// `ListaNfse`, `CompNfse` ou `Nfse`. XML malformado não é recusado aqui: o erro fica para a desserialização.
+fn raiz_nao_nfse(contents: &str) -> Option<String> {
+    let mut reader = quick_xml::Reader::from_str(contents);
+    let mut raiz = RaizDocumento::default();
+    loop {
+        match reader.read_event() {
+            Ok(quick_xml::events::Event::Start(e) | quick_xml::events::Event::Empty(e)) => {
+                if raiz.observar(e.local_name().as_ref()) {
+                    return None;
                 }
-            });
+            }
+            Ok(quick_xml::events::Event::Eof) => return raiz.nao_nfse(),
+            Ok(_) => {}
+            Err(_) => return None,
+        }
+    }
+}
 
-            // Exibe os arquivos selecionados.
-            ui.group(|ui| {
-                ui.label("Arquivos Selecionados:");
-                if self.selected_files.is_empty() {
-                    ui.label("Nenhum arquivo selecionado.");
-                } else {
-                    for path in &self.selected_files {
-                        ui.label(format!("{}", path.display()));
-                    }
-                }
-            });
+/// Acompanha os elementos lidos de um documento para decidir, como em `raiz_nao_nfse`, se ele é uma NFS-e.
+#[derive(Default)]
+struct RaizDocumento {
+    /// Nome do primeiro elemento do documento.
+    raiz: Option<String>,
+    /// Algum elemento de NFS-e já foi encontrado.
+    nfse: bool,
+}
 
-            // Exibe mensagens de erro, se houver.
-            if let Some(msg) = &self.error_message {
-                ui.colored_label(egui::Color32::RED, msg);
+impl RaizDocumento {
+    /// Registra um elemento pelo nome local e indica se o documento já foi reconhecido como NFS-e.
+    fn observar(&mut self, nome: &[u8]) -> bool {
+        let nome = String::from_utf8_lossy(nome);
+        if RAIZES_NFSE.contains(&nome.as_ref()) || nome == "ListaNfse" {
+            self.nfse = true;
+        }
+        self.raiz.get_or_insert_with(|| nome.into_owned());
+        self.nfse
+    }
+
+    /// Nome do elemento raiz, se o documento terminou sem nenhum elemento de NFS-e.
+    fn nao_nfse(self) -> Option<String> {
+        if self.nfse {
+            None
+        } else {
+            self.raiz
+        }
+    }
+}
+
+/// Resultado de um documento recusado por não ser uma NFS-e.
+fn documento_nao_nfse(file_path: &Path, raiz: &str) -> DocumentResult {
+    DocumentResult {
+        source: file_path.to_path_buf(),
+        result: Err(DocumentError::NotNfse(format!("Arquivo não é uma NFS-e (elemento raiz <{}>)", raiz))),
+    }
+}
+
+/// Nome local do elemento raiz do documento; `None` se não houver nenhum elemento ou o XML estiver malformado antes dele.
+fn elemento_raiz(contents: &str) -> Option<String> {
+    let mut reader = quick_xml::Reader::from_str(contents);
+    loop {
+        match reader.read_event() {
+            Ok(quick_xml::events::Event::Start(e) | quick_xml::events::Event::Empty(e)) => {
+                return Some(String::from_utf8_lossy(e.local_name().as_ref()).into_owned());
             }
+            Ok(quick_xml::events::Event::Eof) | Err(_) => return None,
+            Ok(_) => {}
+        }
+    }
+}
 
-            // Exibe o número de notas fiscais processadas.
-            ui.label(format!("Notas Fiscais Processadas: {}", self.parsed_invoices.len()));
-
-            // Exibe os detalhes de cada nota fiscal em uma área de rolagem.
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                for invoice in &self.parsed_invoices {
-                    ui.group(|ui| {
-                        ui.label(format!("Número: {}", invoice.numero));
-                        ui.label(format!("Data de Emissão: {}", invoice.data_emissao));
-                        ui.label(format!("Prestador: {}", invoice.prestador_servico.razao_social));
-                        ui.label(format!("CNPJ Prestador: {}", invoice.prestador_servico.identificacao_prestador.cnpj));
-                        ui.label(format!("Tomador: {}", invoice.tomador_servico.razao_social));
-                        if let Some(cnpj) = &invoice.tomador_servico.identificacao_tomador.cpf_cnpj.cnpj {
-                            ui.label(format!("CNPJ Tomador: {}", cnpj));
-                        }
-                        if let Some(cpf) = &invoice.tomador_servico.identificacao_tomador.cpf_cnpj.cpf {
-                            ui.label(format!("CPF Tomador: {}", cpf));
-                        }
-                        ui.label(format!("Valor: {:.2}", invoice.servico.valores.valor_servicos));
-                        ui.label(format!("Descrição: {}", invoice.servico.discriminacao));
-                    });
-                }
-            });
-        });
+/// Localiza os elementos `CompNfse` do documento e devolve o trecho de XML de cada um,
+/// junto com a posição em que ele começa no documento.
+fn split_comp_nfse(contents: &str) -> Result<Vec<(usize, &str)>, quick_xml::Error> {
+    let mut reader = quick_xml::Reader::from_str(contents);
+    let mut fragments = Vec::new();
+    loop {
+        let start = reader.buffer_position();
+        match reader.read_event()? {
+            quick_xml::events::Event::Start(e) if e.local_name().as_ref() == b"CompNfse" => {
+                reader.read_to_end(e.name())?;
+                fragments.push((start, &contents[start..reader.buffer_position()]));
+            }
+            quick_xml::events::Event::Eof => break,
+            _ => {}
+        }
     }
+    Ok(fragments)
 }
 
-impl TemplateApp {
-    /// Processa a lista de arquivos XML selecionados.
-    fn process_files(&mut self) {
-        self.parsed_invoices.clear();
-        self.error_message = None;
+/// Decodifica e desserializa o conteúdo de um documento XML já carregado em memória.
+/// Aceita tanto a resposta de consulta (`ConsultarNfseResposta`) quanto uma nota isolada
+/// (`CompNfse` ou `Nfse`), devolvendo sempre a lista de notas encontradas.
+fn parse_xml_bytes(bytes: &[u8], file_path: &Path) -> Result<Vec<InfNfse>, String> {
+    // Decodifica o conteúdo conforme o encoding declarado.
+    let contents = decode_xml(bytes);
 
-        for path in &self.selected_files {
-            match parse_xml_from_file(path) {
-                Ok(resposta) => {
-                    for comp_nfse in resposta.lista_nfse.comp_nfse {
-                        self.parsed_invoices.push(comp_nfse.nfse.inf_nfse);
-                    }
-                }
-                Err(e) => {
-                    self.error_message = Some(format!("Erro ao processar {}: {}", path.display(), e));
-                    break;
-                }
+    // Remove o BOM (Byte Order Mark) do início do arquivo, se existir.
+    let contents = contents.trim_start_matches('\u{feff}');
+
+    // Desserializa o conteúdo XML para a estrutura de dados.
+    let resposta: Result<ConsultarNfseResposta, _> = quick_xml::de::from_str(contents);
+    let erro = match resposta {
+        Ok(r) => return Ok(r.lista_nfse.comp_nfse.into_iter().map(CompNfse::into_inf_nfse).collect()),
+        Err(e) => e,
+    };
+
+    // Se não for uma resposta de consulta, tenta como nota única.
+    let erro_comp_nfse = match quick_xml::de::from_str::<CompNfse>(contents) {
+        Ok(comp_nfse) => return Ok(vec![comp_nfse.into_inf_nfse()]),
+        Err(e) => e,
+    };
+    let erro_nfse = match quick_xml::de::from_str::<Nfse>(contents) {
+        Ok(nfse) => return Ok(vec![nfse.inf_nfse]),
+        Err(e) => e,
+    };
+
+    // Nenhum formato serviu: vale o erro do formato indicado pelo elemento raiz; nos demais casos,
+    // o da resposta de consulta é o mais informativo.
+    let erro = match elemento_raiz(contents).as_deref() {
+        Some("CompNfse") => erro_comp_nfse,
+        Some("Nfse") => erro_nfse,
+        _ => erro,
+    };
+    Err(format!("Erro ao processar o XML em \"{:?}\": {}", file_path, diagnostico::descrever_erro(&erro, contents, 0)))
+}
+
+/// Resultado da leitura de um documento XML, identificado pelo caminho de origem.
+/// Para entradas de um ZIP, o caminho é o do arquivo compactado seguido do nome da entrada.
+#[derive(Clone)]
+struct DocumentResult {
+    source: PathBuf,
+    result: Result<Vec<InfNfse>, DocumentError>,
+}
+
+/// Motivo pelo qual um documento não pôde ser lido.
+#[derive(Debug, Clone)]
+enum DocumentError {
+    /// XML de outro sistema, que não é uma NFS-e; o arquivo é ignorado, sem contar como erro.
+    NotNfse(String),
+    /// Falha ao ler ou desserializar o documento.
+    Failed(String),
+}
+
+/// Extrai em memória cada entrada `.xml` de um arquivo ZIP e a desserializa.
+/// Entradas que não são XML são ignoradas.
+fn parse_zip_file(file_path: &Path) -> Vec<DocumentResult> {
+    let single_error = |message: String| {
+        vec![DocumentResult { source: file_path.to_path_buf(), result: Err(DocumentError::Failed(message)) }]
+    };
+
+    let file = match fs::File::open(file_path) {
+        Ok(file) => file,
+        Err(e) => return single_error(format!("Erro ao abrir o arquivo \"{:?}\": {}", file_path, e)),
+    };
+    let mut archive = match zip::ZipArchive::new(file) {
+        Ok(archive) => archive,
+        Err(e) => return single_error(format!("Erro ao abrir o ZIP \"{:?}\": {}", file_path, e)),
+    };
+
+    let mut documents = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = match archive.by_index(i) {
+            Ok(entry) => entry,
+            Err(e) => {
+                documents.push(DocumentResult {
+                    source: file_path.to_path_buf(),
+                    result: Err(DocumentError::Failed(format!("Erro ao ler a entrada {} do ZIP: {}", i + 1, e))),
+                });
+                continue;
+            }
+        };
+        let name = match entry.name() {
+            Ok(name) => name.into_owned(),
+            Err(e) => {
+                documents.push(DocumentResult {
+                    source: file_path.to_path_buf(),
+                    result: Err(DocumentError::Failed(format!("Erro ao ler o nome da entrada {} do ZIP: {}", i + 1, e))),
+                });
+                continue;
             }
+        };
+        if entry.is_dir() || !has_xml_extension(Path::new(&name)) {
+            continue;
+        }
+
+        let source = file_path.join(&name);
+        let mut bytes = Vec::new();
+        match entry.read_to_end(&mut bytes) {
+            Ok(_) => documents.extend(parse_xml_document(&bytes, &source)),
+            Err(e) => documents.push(DocumentResult {
+                source,
+                result: Err(DocumentError::Failed(format!("Erro ao ler o arquivo: {}", e))),
+            }),
         }
     }
+    documents
 }
 
-/// Função principal que inicia a aplicação.
-fn main() {
-    let options = NativeOptions::default();
-    // Executa a aplicação nativa com as opções e a estrutura da aplicação.
-    let _ = run_native(
-        "Processador de Notas Fiscais",
-        options,
-        Box::new(|_cc| Ok(Box::new(TemplateApp::default()))),
-    );
+/// Lê um arquivo selecionado, que pode ser um XML ou um ZIP com vários XMLs.
+fn parse_input_file(file_path: &PathBuf) -> Vec<DocumentResult> {
+    if has_zip_extension(file_path) {
+        parse_zip_file(file_path)
+    } else {
+        parse_xml_from_file(file_path)
+    }
+}
+
+/// Indica se o caminho tem a extensão informada, sem diferenciar maiúsculas de minúsculas
+/// (alguns sistemas geram arquivos `.XML` ou `.Zip`).
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension().and_then(|s| s.to_str()).is_some_and(|e| e.eq_ignore_ascii_case(extension))
+}
+
+/// Indica se o caminho tem a extensão de um arquivo XML.
+fn has_xml_extension(path: &Path) -> bool {
+    has_extension(path, "xml")
+}
+
+/// Indica se o caminho tem a extensão de um arquivo ZIP.
+fn has_zip_extension(path: &Path) -> bool {
+    has_extension(path, "zip")
+}
+
+/// Percorre a pasta e subpastas em busca de arquivos XML e ZIP.
+fn find_input_files(folder: &Path) -> Vec<PathBuf> {
+    walk_input_files(folder, true).collect()
+}
+
+/// Itera sobre os arquivos XML e ZIP da pasta e, com `subpastas`, também das subpastas;
+/// se `root` for um arquivo, considera apenas ele.
+fn walk_input_files(root: &Path, subpastas: bool) -> impl Iterator<Item = PathBuf> {
+    let walker = WalkDir::new(root);
+    let walker = if subpastas { walker } else { walker.max_depth(1) };
+    walker
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && (has_xml_extension(e.path()) || has_zip_extension(e.path())))
+        .map(|e| e.into_path())
+}
+
+/// Caminho canônico do arquivo; se ele não puder ser resolvido (por exemplo, foi excluído), o próprio caminho.
+fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Caminho exibido na lista de arquivos: relativo à pasta selecionada quando o arquivo está dentro dela,
+/// senão só o nome do arquivo.
+fn caminho_exibido(path: &Path, pasta: Option<&Path>) -> String {
+    if let Some(relativo) = pasta.and_then(|pasta| path.strip_prefix(pasta).ok()) {
+        if !relativo.as_os_str().is_empty() {
+            return relativo.display().to_string();
+        }
+    }
+    match path.file_name() {
+        Some(nome) => nome.to_string_lossy().into_owned(),
+        None => path.display().to_string(),
+    }
+}
+
+/// Abre o caminho informado com o programa padrão do sistema operacional.
+fn open_with_system(path: &Path) -> Result<(), String> {
+    #[cfg(target_os = "windows")]
+    let result = std::process::Command::new("explorer").arg(path).spawn();
+    #[cfg(target_os = "macos")]
+    let result = std::process::Command::new("open").arg(path).spawn();
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let result = std::process::Command::new("xdg-open").arg(path).spawn();
+
+    result
+        .map(|_| ())
+        .map_err(|e| format!("Erro ao abrir \"{}\": {}", path.display(), e))
+}
+
+/// Abre o XML de origem de uma nota com o programa padrão do sistema.
+/// Explica o problema quando o arquivo não existe mais ou quando a nota veio de dentro de um ZIP.
+fn open_source_xml(source: &Path) -> Result<(), String> {
+    if source.is_file() {
+        return open_with_system(source);
+    }
+    match source.ancestors().skip(1).find(|p| p.is_file()) {
+        Some(zip) => Err(format!(
+            "A nota foi lida de dentro do arquivo compactado \"{}\"; extraia-o para abrir o XML",
+            zip.display()
+        )),
+        None => Err(format!(
+            "O arquivo \"{}\" não foi encontrado. Ele pode ter sido movido ou excluído depois do processamento",
+            source.display()
+        )),
+    }
+}
+
+/// Arquivo que não pôde ser processado, com a mensagem de erro correspondente.
+#[derive(Debug, Clone)]
+struct FileError {
+    path: PathBuf,
+    message: String,
+}
+
+/// Resumo da leitura de um arquivo selecionado, exibido ao lado dele na lista de arquivos.
+#[derive(Debug, Clone, Default)]
+struct ResumoArquivo {
+    /// Notas lidas do arquivo (no ZIP, de todas as entradas), inclusive duplicatas ignoradas.
+    notas: usize,
+    /// Erros dos documentos que falharam.
+    erros: Vec<String>,
+    /// Documentos ignorados por não serem NFS-e.
+    ignorados: usize,
+    /// Data de modificação do arquivo quando ele foi lido, guardada no cache de leitura.
+    modificado: Option<SystemTime>,
+}
+
+impl ResumoArquivo {
+    /// Resume os documentos lidos de um arquivo.
+    fn new(documents: &[DocumentResult]) -> Self {
+        let mut resumo = Self::default();
+        for document in documents {
+            match &document.result {
+                Ok(invoices) => resumo.notas += invoices.len(),
+                Err(DocumentError::NotNfse(_)) => resumo.ignorados += 1,
+                Err(DocumentError::Failed(e)) => resumo.erros.push(e.clone()),
+            }
+        }
+        resumo
+    }
+}
+
+/// Filtros aplicados sobre as notas processadas antes da exibição.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct InvoiceFilters {
+    /// Quando ativo, mostra apenas as notas emitidas entre `date_start` e `date_end`.
+    filter_by_date: bool,
+    date_start: NaiveDate,
+    date_end: NaiveDate,
+    /// Mantém visíveis as notas cuja data não pôde ser interpretada.
+    include_invalid_dates: bool,
+    /// Termo buscado na razão social do prestador, do tomador ou no número da nota.
+    search: String,
+    /// Competência selecionada na lista de competências (chave `aaaa-mm` ou "sem data").
+    competencia: Option<String>,
+    /// Faixa de valor dos serviços, como digitada; campos vazios não limitam a faixa.
+    valor_min: String,
+    valor_max: String,
+    /// Notas canceladas ficam fora da lista e dos totais, a menos que esta opção esteja ativa.
+    include_canceled: bool,
+    /// Tipo de documento do tomador escolhido no resumo; `None` mostra todos.
+    tipo_documento: Option<TipoDocumento>,
+    /// CNPJ do prestador escolhido na lista de prestadores; `None` mostra todos.
+    cnpj_prestador: Option<String>,
+}
+
+impl Default for InvoiceFilters {
+    fn default() -> Self {
+        let today = Local::now().date_naive();
+        Self {
+            filter_by_date: false,
+            date_start: today,
+            date_end: today,
+            include_invalid_dates: true,
+            search: String::new(),
+            competencia: None,
+            valor_min: String::new(),
+            valor_max: String::new(),
+            include_canceled: false,
+            tipo_documento: None,
+            cnpj_prestador: None,
+        }
+    }
+}
+
+impl InvoiceFilters {
+    /// Indica se a nota passa por todos os filtros ativos.
+    fn matches(&self, parsed: &ParsedInvoice) -> bool {
+        if parsed.inf.cancelada && !self.include_canceled {
+            return false;
+        }
+
+        if self.filter_by_date {
+            match parsed.data_emissao {
+                Some(data) => {
+                    if data < self.date_start || data > self.date_end {
+                        return false;
+                    }
+                }
+                None => {
+                    if !self.include_invalid_dates {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        if self
+            .tipo_documento
+            .is_some_and(|tipo| parsed.inf.tomador_servico.identificacao_tomador.cpf_cnpj.tipo() != tipo)
+        {
+            return false;
+        }
+
+        if self
+            .cnpj_prestador
+            .as_ref()
+            .is_some_and(|cnpj| parsed.inf.prestador_servico.identificacao_prestador.cnpj != *cnpj)
+        {
+            return false;
+        }
+
+        if let Some(competencia) = &self.competencia {
+            if agrupamento::chave_competencia(parsed) != *competencia {
+                return false;
+            }
+        }
+
+        let valor = parsed.inf.servico.valores.valor_servicos;
+        if interpretar_valor(&self.valor_min).is_some_and(|min| valor < min)
+            || interpretar_valor(&self.valor_max).is_some_and(|max| valor > max)
+        {
+            return false;
+        }
+
+        let termo = self.search.trim().to_lowercase();
+        if !termo.is_empty() {
+            let invoice = &parsed.inf;
+            let encontrado = invoice.prestador_servico.razao_social.to_lowercase().contains(&termo)
+                || invoice.tomador_servico.nome().is_some_and(|nome| nome.to_lowercase().contains(&termo))
+                || invoice.numero.to_string().contains(&termo);
+            if !encontrado {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Resultados de um arquivo já lido, com a data de modificação que ele tinha na leitura.
+struct CachedFile {
+    modified: SystemTime,
+    documents: Vec<DocumentResult>,
+}
+
+/// Cache de leitura por caminho, compartilhado com a thread de processamento.
+type ParseCache = Arc<Mutex<HashMap<PathBuf, CachedFile>>>;
+
+/// Como `parse_input_file`, mas reaproveita o resultado anterior se o arquivo não mudou desde então.
+fn parse_input_file_cached(path: &PathBuf, cache: &ParseCache) -> Vec<DocumentResult> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+    if let (Some(modified), Ok(cache)) = (modified, cache.lock()) {
+        if let Some(cached) = cache.get(path).filter(|c| c.modified == modified) {
+            return cached.documents.clone();
+        }
+    }
+
+    let documents = parse_input_file(path);
+    if let (Some(modified), Ok(mut cache)) = (modified, cache.lock()) {
+        cache.insert(path.clone(), CachedFile { modified, documents: documents.clone() });
+    }
+    documents
+}
+
+/// Resultado do processamento de um arquivo, enviado pela thread de fundo.
+struct FileResult {
+    /// Posição do arquivo em `Processing::files`.
+    index: usize,
+    documents: Vec<DocumentResult>,
+}
+
+/// Estado de um processamento em andamento na thread de fundo.
+struct Processing {
+    receiver: mpsc::Receiver<FileResult>,
+    /// Arquivos do lote, na ordem da seleção.
+    files: Vec<PathBuf>,
+    /// Resultados recebidos que ainda aguardam os arquivos anteriores.
+    pending: Vec<Option<Vec<DocumentResult>>>,
+    /// Próximo arquivo a ser incorporado à lista de notas.
+    next_index: usize,
+    /// Quantidade de arquivos já processados pela thread.
+    done: usize,
+    /// Momento em que o processamento começou, para medir sua duração.
+    started: Instant,
+}
+
+/// Notas visíveis calculadas no último frame, reaproveitadas enquanto lote, filtros e ordenação não mudam.
+/// Com milhares de notas, refazer filtro e ordenação a cada frame deixaria a interface lenta.
+struct VisibleCache {
+    version: u64,
+    filters: InvoiceFilters,
+    sort: (Option<SortColumn>, bool),
+    indices: Vec<usize>,
+}
+
+/// Varredura de pastas em andamento na thread de fundo.
+struct FolderScan {
+    receiver: mpsc::Receiver<Vec<PathBuf>>,
+    /// Quantidade de arquivos encontrados até o momento, atualizada pela thread.
+    found: Arc<AtomicUsize>,
+    /// Se os arquivos encontrados se somam à seleção atual em vez de substituí-la.
+    append: bool,
+    /// Nenhuma das origens é pasta: são arquivos soltos na janela.
+    somente_arquivos: bool,
+}
+
+/// Colunas da tabela de notas que permitem ordenação.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum SortColumn {
+    Numero,
+    Data,
+    Prestador,
+    Tomador,
+    Valor,
+}
+
+/// Colunas de dados da tabela de notas, que o usuário pode reordenar arrastando o cabeçalho.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum TableColumn {
+    Numero,
+    Data,
+    DiaSemana,
+    Competencia,
+    Prestador,
+    CnpjPrestador,
+    Tomador,
+    DocumentoTomador,
+    Valor,
+    ValorLiquido,
+    Arquivo,
+}
+
+impl TableColumn {
+    /// Ordem padrão das colunas, restaurada por "Resetar ordem das colunas".
+    const PADRAO: [TableColumn; 11] = [
+        TableColumn::Numero,
+        TableColumn::Data,
+        TableColumn::DiaSemana,
+        TableColumn::Competencia,
+        TableColumn::Prestador,
+        TableColumn::CnpjPrestador,
+        TableColumn::Tomador,
+        TableColumn::DocumentoTomador,
+        TableColumn::Valor,
+        TableColumn::ValorLiquido,
+        TableColumn::Arquivo,
+    ];
+
+    /// Título do cabeçalho, ainda sem tradução.
+    fn titulo(self) -> &'static str {
+        match self {
+            TableColumn::Numero => "Número",
+            TableColumn::Data => "Data",
+            TableColumn::DiaSemana => "Dia da Semana",
+            TableColumn::Competencia => "Competência",
+            TableColumn::Prestador => "Prestador",
+            TableColumn::CnpjPrestador => "CNPJ Prestador",
+            TableColumn::Tomador => "Tomador",
+            TableColumn::DocumentoTomador => "CPF/CNPJ Tomador",
+            TableColumn::Valor => "Valor",
+            TableColumn::ValorLiquido => "Valor Líquido",
+            TableColumn::Arquivo => "Arquivo",
+        }
+    }
+
+    /// Ordenação acionada pelo cabeçalho, para as colunas que a permitem.
+    fn sort(self) -> Option<SortColumn> {
+        match self {
+            TableColumn::Numero => Some(SortColumn::Numero),
+            TableColumn::Data => Some(SortColumn::Data),
+            TableColumn::Prestador => Some(SortColumn::Prestador),
+            TableColumn::Tomador => Some(SortColumn::Tomador),
+            TableColumn::Valor => Some(SortColumn::Valor),
+            _ => None,
+        }
+    }
+
+    /// Largura da coluna; a última coluna exibida sempre ocupa o espaço restante.
+    fn largura(self) -> Column {
+        match self {
+            TableColumn::Numero => Column::auto().at_least(60.0),
+            TableColumn::Data => Column::auto().at_least(80.0),
+            TableColumn::Prestador | TableColumn::Tomador => Column::initial(200.0).clip(true),
+            TableColumn::Valor | TableColumn::ValorLiquido => Column::auto().at_least(90.0),
+            TableColumn::Arquivo => Column::initial(160.0).clip(true),
+            TableColumn::DiaSemana | TableColumn::Competencia | TableColumn::CnpjPrestador | TableColumn::DocumentoTomador => {
+                Column::auto()
+            }
+        }
+    }
+
+    /// Coluna equivalente na exportação com escolha de colunas.
+    fn coluna_exportacao(self) -> Option<export::Coluna> {
+        match self {
+            TableColumn::Numero => Some(export::Coluna::Numero),
+            TableColumn::Data => Some(export::Coluna::DataEmissao),
+            TableColumn::DiaSemana => Some(export::Coluna::DiaSemana),
+            TableColumn::Competencia => Some(export::Coluna::Competencia),
+            TableColumn::Prestador => Some(export::Coluna::Prestador),
+            TableColumn::CnpjPrestador => Some(export::Coluna::CnpjPrestador),
+            TableColumn::Tomador => Some(export::Coluna::Tomador),
+            TableColumn::DocumentoTomador => Some(export::Coluna::DocumentoTomador),
+            TableColumn::Valor => Some(export::Coluna::ValorServicos),
+            TableColumn::ValorLiquido => Some(export::Coluna::ValorLiquido),
+            TableColumn::Arquivo => None,
+        }
+    }
+}
+
+/// Forma de apresentação das notas na área principal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ViewMode {
+    /// Tabela com uma linha por nota.
+    List,
+    /// Notas agrupadas por prestador, com subtotais.
+    ByPrestador,
+    /// Notas agrupadas pelo item da lista de serviços, com subtotais.
+    ByItemServico,
+    /// Notas agrupadas pelo CPF/CNPJ do tomador, com subtotais.
+    ByTomador,
+    /// Gráfico de barras do faturamento por competência.
+    Chart,
+}
+
+impl ViewMode {
+    const ALL: [ViewMode; 5] = [
+        ViewMode::List,
+        ViewMode::ByPrestador,
+        ViewMode::ByTomador,
+        ViewMode::ByItemServico,
+        ViewMode::Chart,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ViewMode::List => tr("Lista plana"),
+            ViewMode::ByPrestador => tr("Agrupado por prestador"),
+            ViewMode::ByItemServico => tr("Agrupado por item de serviço"),
+            ViewMode::ByTomador => tr("Agrupado por tomador"),
+            ViewMode::Chart => tr("Gráfico por mês"),
+        }
+    }
+
+    /// Títulos das colunas de chave e nome no resumo exportado; `None` para a lista plana.
+    fn group_headers(self) -> Option<&'static [&'static str]> {
+        match self {
+            ViewMode::List | ViewMode::Chart => None,
+            ViewMode::ByPrestador => Some(&["CNPJ Prestador", "Prestador"]),
+            ViewMode::ByItemServico => Some(&["Item da Lista de Serviço", "Descrição"]),
+            ViewMode::ByTomador => Some(&["CPF/CNPJ Tomador", "Tomador"]),
+        }
+    }
+
+    /// Agrupa as notas indicadas pelo critério do modo; `None` para os modos sem agrupamento.
+    fn agrupar(self, invoices: &[ParsedInvoice], indices: &[usize]) -> Option<Vec<agrupamento::Grupo>> {
+        match self {
+            ViewMode::List | ViewMode::Chart => None,
+            ViewMode::ByPrestador => Some(agrupamento::por_prestador(invoices, indices)),
+            ViewMode::ByItemServico => Some(agrupamento::por_item_servico(invoices, indices)),
+            ViewMode::ByTomador => Some(agrupamento::por_tomador(invoices, indices)),
+        }
+    }
+}
+
+/// Tamanho da janela na primeira execução e ao resetar o layout.
+const DEFAULT_WINDOW_SIZE: [f32; 2] = [1024.0, 600.0];
+
+/// Chave usada para guardar as preferências no armazenamento do eframe.
+const SETTINGS_KEY: &str = "settings";
+
+/// Tolerância padrão na conferência da soma dos valores da nota: um centavo.
+const DEFAULT_TOLERANCIA_SOMA: f32 = 0.01;
+
+/// Limiar padrão de valor atípico: notas acima da média mais três desvios padrão são destacadas.
+const DEFAULT_LIMIAR_ATIPICO: f32 = 3.0;
+
+/// Intervalo entre as verificações de arquivos modificados no disco desde o processamento.
+const INTERVALO_DESATUALIZADOS: Duration = Duration::from_secs(2);
+
+/// Preferências mantidas entre execuções do programa.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct Settings {
+    /// Última pasta usada nos diálogos de seleção, que passa a ser o diretório inicial deles.
+    last_dir: Option<PathBuf>,
+    /// Arquivos do último lote processado.
+    last_files: Vec<PathBuf>,
+    /// Reabre automaticamente o último lote ao iniciar o programa.
+    reopen_last_batch: bool,
+    /// Tema escolhido pelo usuário; `None` segue o tema do sistema.
+    dark_mode: Option<bool>,
+    /// Idioma da interface.
+    language: Lang,
+    /// Diferença máxima, em reais, aceita entre o valor dos serviços e a soma do líquido com o ISS.
+    tolerancia_soma: f32,
+    /// Quantos desvios padrão acima da média o valor da nota precisa estar para ser destacado como atípico.
+    limiar_atipico: f32,
+    /// Anotações do usuário, pelo identificador da nota; valem também quando a nota é carregada de novo.
+    anotacoes: HashMap<String, String>,
+    /// Inclui os arquivos das subpastas ao varrer ou monitorar uma pasta; desligado, apenas o primeiro nível.
+    incluir_subpastas: bool,
+    /// Colunas escolhidas no diálogo de exportação, na ordem em que foram marcadas.
+    colunas_exportacao: Vec<export::Coluna>,
+    /// Ordem das colunas da tabela de notas, escolhida arrastando os cabeçalhos.
+    ordem_colunas: Vec<TableColumn>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            last_dir: None,
+            last_files: Vec::new(),
+            reopen_last_batch: true,
+            dark_mode: None,
+            language: Lang::default(),
+            tolerancia_soma: DEFAULT_TOLERANCIA_SOMA,
+            limiar_atipico: DEFAULT_LIMIAR_ATIPICO,
+            anotacoes: HashMap::new(),
+            incluir_subpastas: true,
+            colunas_exportacao: export::Coluna::PADRAO.to_vec(),
+            ordem_colunas: TableColumn::PADRAO.to_vec(),
+        }
+    }
+}
+
+/// Estrutura principal da aplicação de GUI.
+struct TemplateApp {
+    selected_files: Vec<PathBuf>,
+    /// Seleção que originou as notas atuais; difere de `selected_files` quando é preciso reprocessar.
+    processed_files: Vec<PathBuf>,
+    /// Resultados já lidos de cada arquivo, reaproveitados enquanto o arquivo não for modificado.
+    parse_cache: ParseCache,
+    /// Pasta escolhida em "Selecionar Pasta", que pode ser monitorada.
+    selected_folder: Option<PathBuf>,
+    /// Monitoramento da pasta em andamento, se houver.
+    watcher: Option<monitor::FolderWatcher>,
+    parsed_invoices: Vec<ParsedInvoice>,
+    /// Erros de processamento, um por arquivo que falhou.
+    file_errors: Vec<FileError>,
+    /// Arquivos lidos com sucesso, mas que não trouxeram nenhuma nota.
+    empty_files: Vec<PathBuf>,
+    /// XMLs ignorados por não serem NFS-e, com o motivo.
+    ignored_files: Vec<FileError>,
+    /// Resultado da leitura de cada arquivo selecionado já processado.
+    resumo_arquivos: HashMap<PathBuf, ResumoArquivo>,
+    /// Arquivos processados que foram modificados no disco desde a leitura.
+    desatualizados: HashSet<PathBuf>,
+    /// Momento da última verificação de `desatualizados`.
+    verificacao_desatualizados: Instant,
+    error_message: Option<String>,
+    info_message: Option<String>,
+    filters: InvoiceFilters,
+    /// Coluna usada na ordenação da tabela; `None` mantém a ordem de processamento.
+    sort_column: Option<SortColumn>,
+    sort_ascending: bool,
+    /// Índices em `parsed_invoices` das notas com janela de detalhes aberta.
+    detail_windows: Vec<usize>,
+    /// Varredura de pasta em andamento, se houver.
+    scan: Option<FolderScan>,
+    /// Processamento em andamento, se houver.
+    processing: Option<Processing>,
+    /// Incrementado a cada alteração de `parsed_invoices`, invalidando os caches abaixo.
+    invoices_version: u64,
+    visible_cache: Option<VisibleCache>,
+    /// Competências do lote, calculadas para a versão indicada.
+    competencias_cache: Option<(u64, Vec<agrupamento::Grupo>)>,
+    /// Lacunas na numeração das notas de cada prestador, calculadas para a versão indicada.
+    lacunas_cache: Option<(u64, Vec<agrupamento::Lacunas>)>,
+    /// CNPJ e razão social dos prestadores do lote, em ordem de razão social, calculados para a versão indicada.
+    prestadores_cache: Option<(u64, Vec<(String, String)>)>,
+    /// Média e desvio padrão do valor dos serviços das notas não canceladas, calculados para a versão indicada.
+    estatisticas_cache: Option<(u64, Option<(f64, f64)>)>,
+    /// Quando desativado, notas repetidas (mesmo número e CNPJ do prestador) são ignoradas.
+    allow_duplicates: bool,
+    /// Faz com que novas seleções de arquivos ou pastas se somem ao lote atual em vez de substituí-lo.
+    append_selection: bool,
+    /// Chaves (número, CNPJ do prestador) das notas já incorporadas ao lote.
+    seen_invoices: HashSet<(u32, String)>,
+    /// Quantidade de notas repetidas ignoradas no último processamento.
+    duplicates_ignored: usize,
+    /// Duração do último processamento completo e quantidade de arquivos do lote.
+    last_processing: Option<(Duration, usize)>,
+    view_mode: ViewMode,
+    /// Exporta apenas as notas que passam pelos filtros ativos, em vez do lote inteiro.
+    export_filtered: bool,
+    /// Preferências persistidas entre execuções.
+    settings: Settings,
+    /// Log do processamento gravado em arquivo.
+    log: registro::Registro,
+    /// Notas marcadas na tabela para cópia, pelos índices em `parsed_invoices`.
+    checked_invoices: HashSet<usize>,
+    /// Janela de comparação entre dois lotes, quando aberta.
+    comparacao: Option<comparacao::Comparacao>,
+    /// Janela "Sobre" aberta.
+    show_about: bool,
+    /// Diálogo de exportação com escolha de colunas aberto.
+    show_export_columns: bool,
+    /// Pedido de fechamento aguardando confirmação, por haver processamento em andamento.
+    confirm_close: bool,
+    /// O usuário confirmou a saída; o próximo pedido de fechamento não é mais interceptado.
+    close_confirmed: bool,
+    /// O processamento em andamento foi pedido por "Recarregar"; ao terminar, o usuário é avisado.
+    reloading: bool,
+    /// A seleção é um lote novo (arquivos ou pasta escolhidos sem acrescentar, seleção vazia ou lote reaberto ao iniciar):
+    /// ao terminar o processamento, o filtro de datas sugere a competência predominante. Reprocessamentos do mesmo
+    /// lote (remoção de arquivos, duplicatas, recarga) e sessões abertas, que trazem os próprios filtros, não sugerem.
+    lote_novo: bool,
+}
+
+impl Default for TemplateApp {
+    /// Cria uma nova instância da aplicação.
+    fn default() -> Self {
+        Self {
+            selected_files: Vec::new(),
+            processed_files: Vec::new(),
+            parse_cache: ParseCache::default(),
+            selected_folder: None,
+            watcher: None,
+            parsed_invoices: Vec::new(),
+            file_errors: Vec::new(),
+            empty_files: Vec::new(),
+            ignored_files: Vec::new(),
+            resumo_arquivos: HashMap::new(),
+            desatualizados: HashSet::new(),
+            verificacao_desatualizados: Instant::now(),
+            error_message: None,
+            info_message: None,
+            filters: InvoiceFilters::default(),
+            sort_column: None,
+            sort_ascending: true,
+            detail_windows: Vec::new(),
+            scan: None,
+            processing: None,
+            invoices_version: 0,
+            visible_cache: None,
+            competencias_cache: None,
+            prestadores_cache: None,
+            lacunas_cache: None,
+            estatisticas_cache: None,
+            allow_duplicates: false,
+            append_selection: false,
+            seen_invoices: HashSet::new(),
+            duplicates_ignored: 0,
+            last_processing: None,
+            view_mode: ViewMode::List,
+            export_filtered: true,
+            settings: Settings::default(),
+            log: registro::Registro::abrir(),
+            checked_invoices: HashSet::new(),
+            comparacao: None,
+            show_about: false,
+            show_export_columns: false,
+            confirm_close: false,
+            close_confirmed: false,
+            reloading: false,
+            lote_novo: false,
+        }
+    }
+}
+
+impl TemplateApp {
+    /// Cria a aplicação restaurando as preferências salvas e, se configurado, o último lote.
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut app = Self::default();
+        if let Some(storage) = cc.storage {
+            app.settings = eframe::get_value(storage, SETTINGS_KEY).unwrap_or_default();
+        }
+        // Uma ordem salva por outra versão, com colunas a mais ou a menos, volta ao padrão.
+        let ordem = &app.settings.ordem_colunas;
+        if ordem.len() != TableColumn::PADRAO.len() || !TableColumn::PADRAO.iter().all(|c| ordem.contains(c)) {
+            app.settings.ordem_colunas = TableColumn::PADRAO.to_vec();
+        }
+        app.apply_theme(&cc.egui_ctx);
+        if app.settings.reopen_last_batch && !app.settings.last_files.is_empty() {
+            app.selected_files = app.settings.last_files.clone();
+            app.lote_novo = true;
+        }
+        app
+    }
+}
+
+impl eframe::App for TemplateApp {
+    /// Salva as preferências ao encerrar (e periodicamente, pelo próprio eframe).
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.settings.last_files = self.selected_files.clone();
+        eframe::set_value(storage, SETTINGS_KEY, &self.settings);
+    }
+
+    /// Atualiza a interface gráfica a cada frame.
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        i18n::set_lang(self.settings.language);
+        self.handle_shortcuts(ctx);
+        self.handle_dropped_files(ctx);
+        self.poll_scan();
+        // Qualquer mudança na seleção (remoção, arquivos arrastados, nova pasta) refaz o lote.
+        if self.selected_files != self.processed_files {
+            self.process_files();
+        }
+        self.poll_processing();
+        if self.scan.is_some() || self.processing.is_some() {
+            // Mantém o indicador de progresso atualizado enquanto a thread trabalha.
+            ctx.request_repaint_after(Duration::from_millis(50));
+        }
+        self.poll_watcher();
+        if self.watcher.is_some() {
+            // Sem eventos de entrada a interface não redesenha; verifica a pasta periodicamente.
+            ctx.request_repaint_after(Duration::from_millis(250));
+        }
+        self.poll_desatualizados();
+        if !self.processed_files.is_empty() {
+            ctx.request_repaint_after(INTERVALO_DESATUALIZADOS);
+        }
+
+        // Janelas flutuantes com os detalhes das notas abertas; várias podem ficar abertas ao mesmo tempo.
+        let mut closed = Vec::new();
+        let mut open_error = None;
+        let mut espelho = None;
+        let tolerancia_soma = self.settings.tolerancia_soma;
+        for &index in &self.detail_windows {
+            let parsed = &mut self.parsed_invoices[index];
+            let anotacoes = &mut self.settings.anotacoes;
+            let mut open = true;
+            egui::Window::new(trf!("Nota {}", parsed.inf.numero))
+                .id(egui::Id::new(("invoice_details", index)))
+                .open(&mut open)
+                .default_width(360.0)
+                .resizable(true)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        if let Some(e) = show_invoice_details(ui, parsed, tolerancia_soma) {
+                            open_error = Some(e);
+                        }
+                        show_anotacao(ui, parsed, anotacoes);
+                        if ui
+                            .button(tr("Gerar espelho PDF…"))
+                            .on_hover_text(tr("PDF de uma página com todos os dados da nota, para imprimir ou anexar"))
+                            .clicked()
+                        {
+                            espelho = Some(index);
+                        }
+                    });
+                });
+            if !open {
+                closed.push(index);
+            }
+        }
+        self.detail_windows.retain(|index| !closed.contains(index));
+        if open_error.is_some() {
+            self.error_message = open_error;
+        }
+        if let Some(index) = espelho {
+            self.export_espelho_pdf(index);
+        }
+
+        let export_count = self.invoices_to_export_count();
+
+        // Barra de menus com as ações de arquivo, exportação e ajuda.
+        egui::TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button(tr("Arquivo"), |ui| {
+                    let open_files = egui::Button::new(tr("Selecionar arquivos XML…"))
+                        .shortcut_text(ctx.format_shortcut(&SHORTCUT_OPEN_FILES));
+                    if ui.add(open_files).clicked() {
+                        ui.close_menu();
+                        self.select_files();
+                    }
+                    let open_folder = egui::Button::new(tr("Selecionar pasta…"))
+                        .shortcut_text(ctx.format_shortcut(&SHORTCUT_OPEN_FOLDER));
+                    if ui.add(open_folder).clicked() {
+                        ui.close_menu();
+                        self.select_folder();
+                    }
+                    if ui.button(tr("Comparar lotes…")).clicked() {
+                        ui.close_menu();
+                        self.comparacao.get_or_insert_with(Default::default);
+                    }
+                    if ui.button(tr("Abrir sessão…")).clicked() {
+                        ui.close_menu();
+                        self.open_session();
+                    }
+                    if ui.add_enabled(!self.selected_files.is_empty(), egui::Button::new(tr("Salvar sessão…"))).clicked() {
+                        ui.close_menu();
+                        self.save_session();
+                    }
+                    ui.checkbox(&mut self.append_selection, tr("Acrescentar ao lote atual"))
+                        .on_hover_text(tr("Novos arquivos e pastas selecionados são somados às notas já carregadas"));
+                    ui.checkbox(&mut self.settings.incluir_subpastas, tr("Incluir subpastas"))
+                        .on_hover_text(tr("Desligado, apenas os arquivos do primeiro nível da pasta são importados"));
+                    let clear = egui::Button::new(tr("Limpar tudo")).shortcut_text(ctx.format_shortcut(&SHORTCUT_CLEAR_ALL));
+                    if ui.add_enabled(!self.selected_files.is_empty(), clear).clicked() {
+                        ui.close_menu();
+                        self.clear_all();
+                    }
+                    let reload = egui::Button::new(tr("Recarregar")).shortcut_text(ctx.format_shortcut(&SHORTCUT_RELOAD));
+                    if ui.add_enabled(!self.selected_files.is_empty(), reload).clicked() {
+                        ui.close_menu();
+                        self.reload();
+                    }
+                    ui.separator();
+                    // Alterar a regra de duplicatas exige refazer o lote.
+                    if ui.checkbox(&mut self.allow_duplicates, tr("Permitir duplicatas")).changed() {
+                        self.process_files();
+                    }
+                    ui.checkbox(&mut self.settings.reopen_last_batch, tr("Reabrir último lote ao iniciar"));
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Tolerância da soma (R$):"));
+                        ui.add(
+                            egui::DragValue::new(&mut self.settings.tolerancia_soma)
+                                .speed(0.01)
+                                .range(0.0..=100.0)
+                                .fixed_decimals(2),
+                        )
+                        .on_hover_text(tr("Diferença aceita entre o valor dos serviços e a soma do valor líquido com o ISS"));
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Valor atípico acima de (desvios padrão):"));
+                        ui.add(
+                            egui::DragValue::new(&mut self.settings.limiar_atipico)
+                                .speed(0.1)
+                                .range(1.0..=10.0)
+                                .fixed_decimals(1),
+                        )
+                        .on_hover_text(tr("Notas com valor acima da média do lote mais este número de desvios padrão são destacadas em amarelo"));
+                    });
+                    ui.separator();
+                    if ui.button(tr("Sair")).clicked() {
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                });
+
+                ui.menu_button(tr("Exportar"), |ui| {
+                    let has_invoices = export_count > 0;
+                    let csv = egui::Button::new(tr("CSV…")).shortcut_text(ctx.format_shortcut(&SHORTCUT_EXPORT_CSV));
+                    if ui.add_enabled(has_invoices, csv).clicked() {
+                        ui.close_menu();
+                        self.export_csv(&export::Coluna::PADRAO);
+                    }
+                    if ui.add_enabled(has_invoices, egui::Button::new(tr("Planilha XLSX…"))).clicked() {
+                        ui.close_menu();
+                        self.export_xlsx(&export::Coluna::PADRAO);
+                    }
+                    if ui.add_enabled(has_invoices, egui::Button::new(tr("Escolher colunas…"))).clicked() {
+                        ui.close_menu();
+                        self.show_export_columns = true;
+                    }
+                    if ui.add_enabled(has_invoices, egui::Button::new(tr("JSON…"))).clicked() {
+                        ui.close_menu();
+                        self.export_json();
+                    }
+                    if ui.add_enabled(has_invoices, egui::Button::new(tr("Relatório PDF…"))).clicked() {
+                        ui.close_menu();
+                        self.export_pdf();
+                    }
+                    if ui.add_enabled(has_invoices, egui::Button::new(tr("TXT posicional (contabilidade)…"))).clicked() {
+                        ui.close_menu();
+                        self.export_txt_posicional();
+                    }
+                    if ui
+                        .add_enabled(has_invoices, egui::Button::new(tr("Dividir em arquivos XML…")))
+                        .on_hover_text(tr("Grava cada nota num arquivo XML próprio, nomeado pelo número da nota"))
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.export_xml_individuais();
+                    }
+                    ui.separator();
+                    if ui.add_enabled(has_invoices, egui::Button::new(tr("Resumo por prestador (CSV)…"))).clicked() {
+                        ui.close_menu();
+                        self.export_resumo_csv(ViewMode::ByPrestador, "Salvar resumo por prestador", "resumo_prestadores.csv");
+                    }
+                    if ui.add_enabled(has_invoices, egui::Button::new(tr("Resumo por item de serviço (CSV)…"))).clicked() {
+                        ui.close_menu();
+                        self.export_resumo_csv(ViewMode::ByItemServico, "Salvar resumo por item de serviço", "resumo_itens_servico.csv");
+                    }
+                    let has_problems =
+                        !self.file_errors.is_empty() || !self.empty_files.is_empty() || !self.ignored_files.is_empty();
+                    if ui
+                        .add_enabled(has_problems, egui::Button::new(tr("Lista de erros (CSV)…")))
+                        .on_hover_text(tr("Arquivos com erro, sem notas ou ignorados, para enviar a quem gerou os XMLs"))
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.export_erros_csv();
+                    }
+                });
+
+                ui.menu_button(tr("Exibir"), |ui| {
+                    // Alterna entre os temas claro e escuro.
+                    let dark = ui.visuals().dark_mode;
+                    let label = if dark { tr("☀ Usar tema claro") } else { tr("🌙 Usar tema escuro") };
+                    if ui.button(label).clicked() {
+                        ui.close_menu();
+                        self.settings.dark_mode = Some(!dark);
+                        self.apply_theme(ctx);
+                    }
+                    if ui.button(tr("Resetar layout")).on_hover_text(tr("Volta a janela e os painéis ao tamanho padrão")).clicked() {
+                        ui.close_menu();
+                        reset_layout(ctx);
+                        self.apply_theme(ctx);
+                    }
+                    if ui.button(tr("Resetar ordem das colunas")).clicked() {
+                        ui.close_menu();
+                        self.settings.ordem_colunas = TableColumn::PADRAO.to_vec();
+                        self.alinhar_colunas_exportacao();
+                    }
+                    ui.menu_button(tr("Idioma"), |ui| {
+                        for lang in Lang::ALL {
+                            if ui.radio_value(&mut self.settings.language, lang, lang.label()).clicked() {
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                });
+
+                ui.menu_button(tr("Ajuda"), |ui| {
+                    // Abre o log do processamento no editor padrão do sistema.
+                    let log_path = self.log.caminho().map(Path::to_path_buf);
+                    if ui.add_enabled(log_path.is_some(), egui::Button::new(tr("Abrir log"))).clicked() {
+                        ui.close_menu();
+                        if let Some(Err(e)) = log_path.as_deref().map(open_with_system) {
+                            self.error_message = Some(e);
+                        }
+                    }
+                    if ui.button(tr("Sobre")).clicked() {
+                        ui.close_menu();
+                        self.show_about = true;
+                    }
+                });
+            });
+        });
+
+        self.handle_close_request(ctx);
+        self.show_comparacao(ctx);
+        self.show_export_columns(ctx);
+
+        egui::Window::new(tr("Sobre"))
+            .open(&mut self.show_about)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.heading(tr("Processador de Notas Fiscais"));
+                ui.label(trf!("Versão {}", env!("CARGO_PKG_VERSION")));
+                ui.label(tr("Visualizador de XML de Nota Fiscal de Serviço Eletrônica (NFS-e) da Nota Carioca."));
+                ui.hyperlink_to(tr("Código-fonte"), "https://github.com/realdyan/vizualizador-xml-nota-carioca-rj");
+            });
+
+        // Rodapé com o escopo das exportações.
+        egui::TopBottomPanel::bottom("export_scope").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(tr("Exportar:"));
+                ui.radio_value(&mut self.export_filtered, true, tr("notas filtradas"));
+                ui.radio_value(&mut self.export_filtered, false, tr("todas as notas"));
+                ui.separator();
+                ui.label(trf!("Exportando {} de {} notas", export_count, self.parsed_invoices.len()));
+            });
+        });
+
+        // Totais das notas filtradas, sempre visíveis independentemente da rolagem da lista.
+        let filters_before = self.filters.clone();
+        if !self.parsed_invoices.is_empty() {
+            let visible = self.cached_visible_indices();
+            let valores = || visible.iter().map(|&i| &self.parsed_invoices[i].inf.servico.valores);
+            let total = somar_valores(valores().map(|v| v.valor_servicos));
+            let total_iss = somar_valores(valores().filter_map(|v| v.valor_iss));
+            egui::TopBottomPanel::bottom("totals_bar").show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(trf!("Quantidade: {}", visible.len()));
+                    ui.separator();
+                    ui.strong(trf!("Total: {}", formatar_moeda(total)));
+                    ui.separator();
+                    ui.label(trf!("Total ISS: {}", formatar_moeda(total_iss)));
+                });
+            });
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading(tr("Processador de Notas Fiscais"));
+
+            // Monitoramento da pasta selecionada: novos XMLs são importados automaticamente.
+            ui.horizontal(|ui| {
+                let mut stop = false;
+                if let Some(watcher) = &mut self.watcher {
+                    if watcher.paused {
+                        ui.colored_label(egui::Color32::ORANGE, tr("⏸ Monitoramento pausado:"));
+                    } else {
+                        ui.colored_label(egui::Color32::DARK_GREEN, tr("● Monitorando:"));
+                    }
+                    ui.label(watcher.folder().display().to_string());
+                    let label = if watcher.paused { tr("Retomar") } else { tr("Pausar") };
+                    if ui.button(label).clicked() {
+                        watcher.paused = !watcher.paused;
+                    }
+                    if ui.button(tr("Parar")).clicked() {
+                        stop = true;
+                    }
+                } else if let Some(folder) = self.selected_folder.clone() {
+                    if ui
+                        .button(tr("Monitorar pasta"))
+                        .on_hover_text(tr("Importa automaticamente os XMLs que chegarem na pasta selecionada"))
+                        .clicked()
+                    {
+                        match monitor::FolderWatcher::start(&folder, self.settings.incluir_subpastas) {
+                            Ok(watcher) => self.watcher = Some(watcher),
+                            Err(e) => self.error_message = Some(e),
+                        }
+                    }
+                }
+                if stop {
+                    self.watcher = None;
+                }
+            });
+
+            // Indica que a varredura de pastas ainda está em andamento.
+            if let Some(scan) = &self.scan {
+                ui.horizontal(|ui| {
+                    ui.add(egui::Spinner::new());
+                    ui.label(trf!(
+                        "Procurando XMLs... {} arquivo(s) encontrado(s)",
+                        scan.found.load(Ordering::Relaxed)
+                    ));
+                });
+            }
+
+            // Exibe o progresso do processamento em andamento.
+            if let Some(processing) = &self.processing {
+                let total = processing.files.len();
+                ui.add(
+                    egui::ProgressBar::new(processing.done as f32 / total as f32)
+                        .text(trf!("Processando: {} de {} arquivos", processing.done, total))
+                        .animate(true),
+                );
+            }
+
+            // Exibe os arquivos selecionados.
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Arquivos Selecionados:"));
+                    if !self.selected_files.is_empty()
+                        && ui.button(tr("Limpar tudo")).on_hover_text(ctx.format_shortcut(&SHORTCUT_CLEAR_ALL)).clicked()
+                    {
+                        self.clear_all();
+                    }
+                    if !self.selected_files.is_empty()
+                        && ui
+                            .button(tr("Recarregar"))
+                            .on_hover_text(tr("Relê todos os arquivos do disco, mesmo os que não mudaram"))
+                            .clicked()
+                    {
+                        self.reload();
+                    }
+                });
+                if self.selected_files.is_empty() {
+                    ui.label(tr("Nenhum arquivo selecionado."));
+                } else {
+                    let mut removed = None;
+                    let mut recarregar = None;
+                    egui::ScrollArea::vertical()
+                        .id_salt("selected_files")
+                        .max_height(120.0)
+                        .show(ui, |ui| {
+                            for (i, path) in self.selected_files.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    if ui.small_button("✖").on_hover_text(tr("Remover da seleção")).clicked() {
+                                        removed = Some(i);
+                                    }
+                                    ui.label(caminho_exibido(path, self.selected_folder.as_deref()))
+                                        .on_hover_text(path.display().to_string());
+                                    // Durante um processamento o lote está sendo refeito; a recarga espera ele terminar.
+                                    let recarregar_arquivo =
+                                        egui::Button::new(egui::RichText::new("⟳").color(egui::Color32::ORANGE)).small();
+                                    if self.desatualizados.contains(path)
+                                        && ui
+                                            .add_enabled(self.processing.is_none(), recarregar_arquivo)
+                                            .on_hover_text(tr("Arquivo modificado no disco desde o processamento; clique para recarregá-lo"))
+                                            .clicked()
+                                    {
+                                        recarregar = Some(path.clone());
+                                    }
+                                    match self.resumo_arquivos.get(path) {
+                                        Some(resumo) if !resumo.erros.is_empty() => {
+                                            ui.colored_label(egui::Color32::RED, resumo.erros.join("; "));
+                                        }
+                                        Some(resumo) if resumo.notas == 0 && resumo.ignorados > 0 => {
+                                            ui.weak(tr("— ignorado (não é NFS-e)"));
+                                        }
+                                        Some(resumo) => {
+                                            ui.weak(trf!("— {} nota(s)", resumo.notas));
+                                        }
+                                        None => {}
+                                    }
+                                });
+                            }
+                        });
+                    if let Some(i) = removed {
+                        self.selected_files.remove(i);
+                    }
+                    if let Some(path) = recarregar {
+                        self.reload_file(&path);
+                    }
+                }
+            });
+
+            // Exibe mensagens de erro, se houver.
+            if let Some(msg) = &self.error_message {
+                ui.colored_label(egui::Color32::RED, msg);
+            }
+            if !self.file_errors.is_empty() {
+                egui::CollapsingHeader::new(
+                    egui::RichText::new(trf!("{} arquivo(s) com erro", self.file_errors.len()))
+                        .color(egui::Color32::RED),
+                )
+                .default_open(true)
+                .show(ui, |ui| {
+                    for error in &self.file_errors {
+                        ui.colored_label(
+                            egui::Color32::RED,
+                            format!("{}: {}", error.path.display(), error.message),
+                        );
+                    }
+                });
+            }
+            if !self.empty_files.is_empty() {
+                egui::CollapsingHeader::new(
+                    egui::RichText::new(trf!("{} arquivo(s) sem notas", self.empty_files.len()))
+                        .color(egui::Color32::ORANGE),
+                )
+                .show(ui, |ui| {
+                    ui.label(tr("Estes arquivos foram lidos, mas não contêm nenhuma nota (CompNfse):"));
+                    for path in &self.empty_files {
+                        ui.colored_label(egui::Color32::ORANGE, path.display().to_string());
+                    }
+                });
+            }
+            if !self.ignored_files.is_empty() {
+                egui::CollapsingHeader::new(trf!("{} arquivo(s) ignorado(s)", self.ignored_files.len())).show(ui, |ui| {
+                    ui.label(tr("Estes XMLs não são notas fiscais de serviço e foram ignorados:"));
+                    for ignored in &self.ignored_files {
+                        ui.weak(format!("{}: {}", ignored.path.display(), ignored.message));
+                    }
+                });
+            }
+            if let Some(msg) = &self.info_message {
+                ui.colored_label(egui::Color32::DARK_GREEN, msg);
+            }
+
+            // Busca textual por prestador, tomador ou número da nota.
+            ui.horizontal(|ui| {
+                ui.label(tr("Buscar:"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.filters.search)
+                        .hint_text(tr("prestador, tomador ou número da nota")),
+                );
+                if !self.filters.search.trim().is_empty() && ui.button("✖").clicked() {
+                    self.filters.search.clear();
+                }
+                ui.checkbox(&mut self.filters.include_canceled, tr("Incluir canceladas"));
+            });
+
+            // Filtro por intervalo de datas de emissão.
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.filters.filter_by_date, tr("Filtrar por data"));
+                ui.add_enabled_ui(self.filters.filter_by_date, |ui| {
+                    ui.label(tr("De:"));
+                    ui.add(DatePickerButton::new(&mut self.filters.date_start).id_salt("date_start"));
+                    ui.label(tr("Até:"));
+                    ui.add(DatePickerButton::new(&mut self.filters.date_end).id_salt("date_end"));
+                    ui.checkbox(&mut self.filters.include_invalid_dates, tr("Incluir datas inválidas"));
+                });
+            });
+
+            // Filtro por faixa de valor dos serviços.
+            ui.horizontal(|ui| {
+                ui.label(tr("Valor entre:"));
+                for (texto, hint, id) in [
+                    (&mut self.filters.valor_min, tr("mínimo"), "valor_min"),
+                    (&mut self.filters.valor_max, tr("sem limite"), "valor_max"),
+                ] {
+                    ui.add(egui::TextEdit::singleline(texto).id_salt(id).hint_text(hint).desired_width(90.0));
+                    if !texto.trim().is_empty() && interpretar_valor(texto).is_none() {
+                        ui.colored_label(egui::Color32::RED, "⚠").on_hover_text(tr("Valor inválido; o limite será ignorado"));
+                    }
+                }
+                let has_range = !self.filters.valor_min.is_empty() || !self.filters.valor_max.is_empty();
+                if has_range && ui.button("✖").clicked() {
+                    self.filters.valor_min.clear();
+                    self.filters.valor_max.clear();
+                }
+            });
+
+            // Filtro por prestador, escolhido entre os CNPJs presentes no lote.
+            if !self.parsed_invoices.is_empty() {
+                self.show_prestador_filter(ui);
+            }
+
+            // Totais por competência; clicar numa competência filtra a lista por aquele mês.
+            if !self.parsed_invoices.is_empty() {
+                self.show_competencias(ui);
+                self.show_lacunas(ui);
+            }
+
+            let visible = self.cached_visible_indices();
+            if !self.filters.search.trim().is_empty() {
+                ui.label(trf!("{} resultado(s) para \"{}\"", visible.len(), self.filters.search.trim()));
+            }
+
+            // Exibe o número de notas fiscais processadas.
+            if visible.len() == self.parsed_invoices.len() {
+                ui.label(trf!("Notas Fiscais Processadas: {}", visible.len()));
+            } else {
+                ui.label(trf!(
+                    "Notas Fiscais Processadas: {} (de {})",
+                    visible.len(),
+                    self.parsed_invoices.len()
+                ));
+            }
+
+            // Resumo dos valores das notas exibidas.
+            ui.group(|ui| {
+                let total = somar_valores(
+                    visible.iter().map(|&i| self.parsed_invoices[i].inf.servico.valores.valor_servicos),
+                );
+                ui.horizontal(|ui| {
+                    ui.label(trf!("Quantidade: {}", visible.len()));
+                    ui.separator();
+                    ui.strong(trf!("Total: {}", formatar_moeda(total)));
+                    if !visible.is_empty() {
+                        ui.separator();
+                        ui.label(trf!("Média: {}", formatar_moeda(total / visible.len() as f64)));
+                    }
+                    // O total líquido considera apenas as notas que informam o ISS.
+                    let liquidos: Vec<f32> = visible
+                        .iter()
+                        .filter_map(|&i| self.parsed_invoices[i].inf.servico.valores.valor_liquido())
+                        .collect();
+                    if !liquidos.is_empty() {
+                        ui.separator();
+                        let label = ui.label(trf!("Total líquido: {}", formatar_moeda(somar_valores(liquidos.iter().copied()))));
+                        if liquidos.len() < visible.len() {
+                            label.on_hover_text(trf!(
+                                "{} nota(s) sem ISS informado não entram no total líquido",
+                                visible.len() - liquidos.len()
+                            ));
+                        }
+                    }
+                    // Notas sem ISS informado ficam fora da soma, mas são contadas para conferência.
+                    let valores_iss: Vec<f32> = visible
+                        .iter()
+                        .filter_map(|&i| self.parsed_invoices[i].inf.servico.valores.valor_iss)
+                        .collect();
+                    if !visible.is_empty() {
+                        ui.separator();
+                        ui.label(trf!("Total ISS: {}", formatar_moeda(somar_valores(valores_iss.iter().copied()))));
+                        let sem_iss = visible.len() - valores_iss.len();
+                        if sem_iss > 0 {
+                            ui.label(trf!("({} sem ISS)", sem_iss))
+                                .on_hover_text(tr("Notas que não informam o valor do ISS não entram no total de ISS"));
+                        }
+                    }
+                    let invalid_documents = visible
+                        .iter()
+                        .filter(|&&i| !self.parsed_invoices[i].documento_tomador_valido)
+                        .count();
+                    let inconsistent_sums = visible
+                        .iter()
+                        .filter(|&&i| {
+                            let valores = &self.parsed_invoices[i].inf.servico.valores;
+                            valores.soma_consistente(self.settings.tolerancia_soma) == Some(false)
+                        })
+                        .count();
+                    // Conta as canceladas que os demais filtros deixariam passar, como as notas dos totais.
+                    if !self.filters.include_canceled {
+                        let filtros = InvoiceFilters { include_canceled: true, ..self.filters.clone() };
+                        let canceled =
+                            self.parsed_invoices.iter().filter(|p| p.inf.cancelada && filtros.matches(p)).count();
+                        if canceled > 0 {
+                            ui.separator();
+                            ui.label(trf!("Canceladas ignoradas: {}", canceled));
+                        }
+                    }
+                    if !self.empty_files.is_empty() {
+                        ui.separator();
+                        ui.colored_label(
+                            egui::Color32::ORANGE,
+                            trf!("Arquivos sem notas: {}", self.empty_files.len()),
+                        );
+                    }
+                    if self.duplicates_ignored > 0 {
+                        ui.separator();
+                        ui.label(trf!("Duplicadas ignoradas: {}", self.duplicates_ignored));
+                    }
+                    if invalid_documents > 0 {
+                        ui.separator();
+                        ui.colored_label(
+                            egui::Color32::RED,
+                            trf!("Documentos inválidos: {}", invalid_documents),
+                        );
+                    }
+                    if inconsistent_sums > 0 {
+                        ui.separator();
+                        ui.colored_label(egui::Color32::ORANGE, trf!("Somas inconsistentes: {}", inconsistent_sums))
+                            .on_hover_text(tr("Notas em que o valor dos serviços difere da soma do valor líquido com o ISS"));
+                    }
+                });
+                if !self.parsed_invoices.is_empty() {
+                    self.show_tipos_documento(ui);
+                }
+                if let Some((duracao, arquivos)) = self.last_processing {
+                    // Lotes muito rápidos teriam taxa infinita; o mínimo de 1 ms evita a divisão por zero.
+                    let segundos = duracao.as_secs_f64().max(0.001);
+                    ui.weak(trf!(
+                        "Processado em {} ms ({} arquivos/s)",
+                        duracao.as_millis(),
+                        format!("{:.1}", arquivos as f64 / segundos)
+                    ));
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(tr("Visualização:"));
+                egui::ComboBox::from_id_salt("view_mode")
+                    .selected_text(self.view_mode.label())
+                    .show_ui(ui, |ui| {
+                        for mode in ViewMode::ALL {
+                            ui.selectable_value(&mut self.view_mode, mode, mode.label());
+                        }
+                    });
+            });
+
+            let grupos = self.view_mode.agrupar(&self.parsed_invoices, &visible);
+            match (self.view_mode, grupos) {
+                (_, Some(grupos)) => {
+                    if ui.add_enabled(!grupos.is_empty(), egui::Button::new(tr("Exportar resumo CSV"))).clicked() {
+                        self.export_groups_csv(&grupos);
+                    }
+                    self.show_groups(ui, &grupos);
+                }
+                (ViewMode::Chart, None) => {
+                    let grupos = agrupamento::por_competencia(&self.parsed_invoices, &visible);
+                    grafico::faturamento_mensal(ui, &grupos);
+                }
+                // Exibe as notas fiscais em uma tabela ordenável.
+                (_, None) => {
+                    ui.horizontal(|ui| {
+                        let checked: Vec<&ParsedInvoice> = visible
+                            .iter()
+                            .filter(|i| self.checked_invoices.contains(i))
+                            .map(|&i| &self.parsed_invoices[i])
+                            .collect();
+                        let copy = egui::Button::new(trf!("Copiar selecionadas ({})", checked.len()));
+                        if ui
+                            .add_enabled(!checked.is_empty(), copy)
+                            .on_hover_text(tr("Copia as notas marcadas como tabela separada por tabulação, pronta para colar numa planilha"))
+                            .clicked()
+                        {
+                            ui.ctx().copy_text(export::tsv_table(&checked, &export::Coluna::PADRAO));
+                        }
+                        let colunas = &self.settings.colunas_exportacao;
+                        if ui
+                            .add_enabled(!visible.is_empty() && !colunas.is_empty(), egui::Button::new(tr("Copiar tabela (TSV)")))
+                            .on_hover_text(tr("Copia todas as notas exibidas, com as colunas escolhidas em \"Exportar › Escolher colunas…\", para colar numa planilha"))
+                            .clicked()
+                        {
+                            let notas: Vec<&ParsedInvoice> = visible.iter().map(|&i| &self.parsed_invoices[i]).collect();
+                            ui.ctx().copy_text(export::tsv_table(&notas, colunas));
+                            self.info_message = Some(trf!("{} nota(s) copiada(s) para a área de transferência", notas.len()));
+                        }
+                    });
+                    self.show_invoice_table(ui, &visible);
+                }
+            }
+        });
+
+        // O rodapé é desenhado antes da área central; se os filtros mudaram nela, redesenha para atualizá-lo.
+        if self.filters != filters_before {
+            ctx.request_repaint();
+        }
+    }
+}
+
+/// Atalhos de teclado das ações principais; `COMMAND` é Ctrl no Windows e no Linux e Cmd no macOS.
+const SHORTCUT_OPEN_FILES: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::O);
+const SHORTCUT_OPEN_FOLDER: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT), egui::Key::O);
+const SHORTCUT_EXPORT_CSV: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::E);
+const SHORTCUT_CLEAR_ALL: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::L);
+const SHORTCUT_RELOAD: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::F5);
+
+/// Volta a janela ao tamanho padrão e descarta o estado salvo de painéis e seções colapsáveis.
+fn reset_layout(ctx: &egui::Context) {
+    ctx.memory_mut(|memory| *memory = Default::default());
+    ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(DEFAULT_WINDOW_SIZE.into()));
+}
+
+/// Endereço do portal da Nota Carioca, onde a autenticidade da nota pode ser conferida.
+const NOTA_CARIOCA_PORTAL_URL: &str = "https://notacarioca.rio.gov.br/";
+
+/// Altura máxima da lista de notas de um grupo; acima dela a lista rola.
+const GROUP_MAX_HEIGHT: f32 = 300.0;
+
+/// Quantidade de caracteres do identificador exibidos nos detalhes; o hash completo fica na dica e na cópia.
+const IDENTIFICADOR_CHARS: usize = 16;
+
+/// Altura máxima do trecho de XML exibido nos detalhes; acima dela o texto rola.
+const XML_MAX_HEIGHT: f32 = 300.0;
+
+/// Tempo, em segundos, em que a confirmação "Copiado!" fica visível após o clique.
+const COPIED_FEEDBACK_SECONDS: f64 = 1.5;
+
+/// Botão pequeno que copia o texto para a área de transferência.
+fn copy_button(ui: &mut egui::Ui, text: &str) {
+    let response = ui.small_button("📋");
+    let copied_at_id = response.id.with("copied_at");
+    let now = ui.input(|i| i.time);
+    if response.clicked() {
+        ui.ctx().copy_text(text.to_string());
+        ui.ctx().data_mut(|d| d.insert_temp(copied_at_id, now));
+    }
+
+    // Logo após o clique, troca a dica do botão pela confirmação da cópia.
+    let copied_at: Option<f64> = ui.ctx().data(|d| d.get_temp(copied_at_id));
+    match copied_at {
+        Some(copied_at) if now - copied_at < COPIED_FEEDBACK_SECONDS => {
+            response.show_tooltip_text(tr("Copiado!"));
+            ui.ctx().request_repaint_after(std::time::Duration::from_secs_f64(COPIED_FEEDBACK_SECONDS));
+        }
+        _ => {
+            response.on_hover_text(tr("Copiar"));
+        }
+    }
+}
+
+/// Seção colapsável com o endereço, exibida apenas quando o XML traz algum dado dele.
+fn show_endereco(ui: &mut egui::Ui, titulo: &str, endereco: &Option<Endereco>) {
+    let linhas = endereco.as_ref().map(Endereco::linhas).unwrap_or_default();
+    if linhas.is_empty() {
+        return;
+    }
+    egui::CollapsingHeader::new(titulo).show(ui, |ui| {
+        for linha in &linhas {
+            ui.label(linha);
+        }
+        copy_button(ui, &linhas.join("\n"));
+    });
+}
+
+/// Campo de anotação da nota; o texto é guardado nas preferências pelo identificador da nota.
+fn show_anotacao(ui: &mut egui::Ui, parsed: &mut ParsedInvoice, anotacoes: &mut HashMap<String, String>) {
+    ui.horizontal(|ui| {
+        ui.label(tr("Anotação:"));
+        let edit = egui::TextEdit::singleline(&mut parsed.anotacao).hint_text(tr("conferida, pendência…"));
+        if ui.add(edit).changed() {
+            if parsed.anotacao.trim().is_empty() {
+                anotacoes.remove(&parsed.identificador);
+            } else {
+                anotacoes.insert(parsed.identificador.clone(), parsed.anotacao.clone());
+            }
+        }
+    });
+}
+
+/// Texto exibido no lugar da razão social quando o XML não traz o nome do tomador.
+fn tomador_sem_nome() -> egui::RichText {
+    egui::RichText::new(tr("(tomador sem nome)")).italics().color(egui::Color32::GRAY)
+}
+
+/// Conteúdo de uma célula da tabela de notas.
+/// `limite_atipico` é o valor acima do qual a nota é destacada em amarelo, apenas como auxílio à revisão.
+fn show_table_cell(
+    ui: &mut egui::Ui,
+    column: TableColumn,
+    parsed: &ParsedInvoice,
+    tolerancia_soma: f32,
+    limite_atipico: Option<f64>,
+) {
+    let invoice = &parsed.inf;
+    match column {
+        TableColumn::Numero => {
+            if invoice.cancelada {
+                ui.label(canceled_text(invoice.numero.to_string())).on_hover_text(tr("Nota cancelada"));
+            } else {
+                ui.label(invoice.numero.to_string());
+            }
+            if !parsed.anotacao.is_empty() {
+                ui.label("📝").on_hover_text(&parsed.anotacao);
+            }
+        }
+        TableColumn::Data => {
+            if parsed.data_emissao.is_some() {
+                ui.label(parsed.data_emissao_formatada());
+            } else {
+                ui.colored_label(egui::Color32::ORANGE, parsed.data_emissao_formatada())
+                    .on_hover_text(tr("Data inválida"));
+            }
+        }
+        TableColumn::DiaSemana => {
+            ui.label(tr(parsed.dia_semana()));
+        }
+        TableColumn::Competencia => {
+            ui.label(parsed.competencia());
+        }
+        TableColumn::Prestador => {
+            ui.label(&invoice.prestador_servico.razao_social);
+        }
+        TableColumn::CnpjPrestador => {
+            ui.label(formatar_documento(&invoice.prestador_servico.identificacao_prestador.cnpj));
+        }
+        TableColumn::Tomador => {
+            match invoice.tomador_servico.nome() {
+                Some(nome) => ui.label(nome),
+                None => ui.label(tomador_sem_nome()),
+            };
+        }
+        TableColumn::DocumentoTomador => {
+            let identificacao = &invoice.tomador_servico.identificacao_tomador;
+            if !parsed.documento_tomador_valido {
+                ui.colored_label(egui::Color32::RED, "⚠").on_hover_text(tr("Dígito verificador inválido"));
+            }
+            if identificacao.no_exterior() {
+                ui.label("🌐").on_hover_text(tr("Tomador no exterior"));
+                ui.label(identificacao.nif_estrangeiro.as_deref().unwrap_or(""));
+            } else {
+                ui.label(formatar_documento(identificacao.cpf_cnpj.documento().unwrap_or("")));
+            }
+        }
+        TableColumn::Valor => {
+            let valores = &invoice.servico.valores;
+            let valor = formatar_moeda(valores.valor_servicos);
+            if valores.valor_servicos_ausente {
+                ui.colored_label(egui::Color32::ORANGE, "⚠").on_hover_text(tr("Valor dos serviços ausente no XML"));
+            }
+            if valores.soma_consistente(tolerancia_soma) == Some(false) {
+                ui.colored_label(egui::Color32::ORANGE, "⚠")
+                    .on_hover_text(tr("Valor dos serviços difere da soma do valor líquido com o ISS"));
+            }
+            let atipico = limite_atipico.is_some_and(|limite| f64::from(valores.valor_servicos) > limite);
+            if invoice.cancelada {
+                ui.label(canceled_text(valor));
+            } else if atipico {
+                let text = egui::RichText::new(valor).background_color(egui::Color32::YELLOW).color(egui::Color32::BLACK);
+                ui.label(text).on_hover_text(tr("Valor muito acima da média do lote; confira se não houve erro de digitação"));
+            } else {
+                ui.label(valor);
+            }
+        }
+        TableColumn::ValorLiquido => match invoice.servico.valores.valor_liquido() {
+            Some(liquido) => {
+                ui.label(formatar_moeda(liquido));
+            }
+            None => {
+                ui.label("—").on_hover_text(tr("A nota não informa o ISS"));
+            }
+        },
+        TableColumn::Arquivo => {
+            let name = parsed.source.file_name().unwrap_or(parsed.source.as_os_str());
+            ui.label(name.to_string_lossy()).on_hover_text(parsed.source.display().to_string());
+        }
+    }
+}
+
+/// Texto tachado em vermelho, usado para destacar notas canceladas.
+fn canceled_text(text: String) -> egui::RichText {
+    egui::RichText::new(text).strikethrough().color(egui::Color32::RED)
+}
+
+/// Exibe todos os campos de uma nota fiscal.
+/// Retorna a mensagem de erro caso a abertura da pasta do arquivo falhe.
+fn show_invoice_details(ui: &mut egui::Ui, parsed: &ParsedInvoice, tolerancia_soma: f32) -> Option<String> {
+    let mut open_error = None;
+    let invoice = &parsed.inf;
+    ui.group(|ui| {
+        ui.horizontal(|ui| {
+            ui.label(trf!("Arquivo: {}", parsed.source.display()));
+            if ui.small_button(tr("Abrir pasta do arquivo")).clicked() {
+                // Notas vindas de um ZIP têm como origem um caminho dentro do arquivo compactado.
+                let folder = parsed.source.ancestors().skip(1).find(|p| p.is_dir()).unwrap_or(&parsed.source);
+                if let Err(e) = open_with_system(folder) {
+                    open_error = Some(e);
+                }
+            }
+            if ui.small_button(tr("Abrir XML")).on_hover_text(tr("Abre o arquivo no programa padrão do sistema")).clicked() {
+                if let Err(e) = open_source_xml(&parsed.source) {
+                    open_error = Some(e);
+                }
+            }
+        });
+        if invoice.cancelada {
+            ui.colored_label(egui::Color32::RED, tr("⊘ Nota cancelada"));
+        }
+        ui.horizontal(|ui| {
+            let curto = &parsed.identificador[..IDENTIFICADOR_CHARS];
+            ui.label(trf!("Identificador: {}…", curto)).on_hover_text(&parsed.identificador);
+            copy_button(ui, &parsed.identificador);
+        });
+        ui.horizontal(|ui| {
+            ui.label(trf!("Número: {}", invoice.numero));
+            copy_button(ui, &invoice.numero.to_string());
+        });
+        if let Some(codigo) = &invoice.codigo_verificacao {
+            ui.horizontal(|ui| {
+                ui.label(trf!("Código de Verificação: {}", codigo));
+                copy_button(ui, codigo);
+                ui.hyperlink_to(tr("Verificar no portal"), NOTA_CARIOCA_PORTAL_URL);
+            });
+        }
+        if parsed.data_emissao.is_some() {
+            ui.label(trf!("Data de Emissão: {}", parsed.data_emissao_formatada()));
+        } else {
+            ui.colored_label(
+                egui::Color32::ORANGE,
+                trf!("Data de Emissão: {} (data inválida)", parsed.data_emissao_formatada()),
+            );
+        }
+        ui.label(trf!("Prestador: {}", invoice.prestador_servico.razao_social));
+        ui.horizontal(|ui| {
+            let cnpj = &invoice.prestador_servico.identificacao_prestador.cnpj;
+            ui.label(trf!("CNPJ Prestador: {}", formatar_documento(cnpj)));
+            copy_button(ui, cnpj);
+        });
+        show_endereco(ui, tr("Endereço do prestador"), &invoice.prestador_servico.endereco);
+        match invoice.tomador_servico.nome() {
+            Some(nome) => ui.label(trf!("Tomador: {}", nome)),
+            None => ui.horizontal(|ui| ui.label(tr("Tomador:")) | ui.label(tomador_sem_nome())).inner,
+        };
+        if let Some(cnpj) = &invoice.tomador_servico.identificacao_tomador.cpf_cnpj.cnpj {
+            ui.horizontal(|ui| {
+                ui.label(trf!("CNPJ Tomador: {}", formatar_documento(cnpj)));
+                copy_button(ui, cnpj);
+            });
+        }
+        if let Some(cpf) = &invoice.tomador_servico.identificacao_tomador.cpf_cnpj.cpf {
+            ui.horizontal(|ui| {
+                ui.label(trf!("CPF Tomador: {}", formatar_documento(cpf)));
+                copy_button(ui, cpf);
+            });
+        }
+        let identificacao = &invoice.tomador_servico.identificacao_tomador;
+        if let (true, Some(nif)) = (identificacao.no_exterior(), &identificacao.nif_estrangeiro) {
+            ui.horizontal(|ui| {
+                ui.label(trf!("Documento no exterior (NIF): {}", nif));
+                copy_button(ui, nif);
+            });
+            ui.label(tr("🌐 Tomador no exterior"));
+        }
+        if !parsed.documento_tomador_valido {
+            ui.colored_label(egui::Color32::RED, tr("⚠ Documento do tomador com dígito verificador inválido"));
+        }
+        show_endereco(ui, tr("Endereço do tomador"), &invoice.tomador_servico.endereco);
+        ui.label(trf!("Valor: {}", formatar_moeda(invoice.servico.valores.valor_servicos)));
+        if invoice.servico.valores.valor_servicos_ausente {
+            ui.colored_label(egui::Color32::ORANGE, tr("⚠ Valor dos serviços ausente no XML"));
+        }
+        let servico = &invoice.servico;
+        if let Some(item) = &servico.item_lista_servico {
+            ui.label(trf!("Item da Lista de Serviço: {}", item));
+        }
+        if let Some(codigo) = &servico.codigo_tributacao_municipio {
+            ui.label(trf!("Código de Tributação Municipal: {}", codigo));
+        }
+        let valores = &servico.valores;
+        if let Some(base_calculo) = valores.base_calculo {
+            ui.label(trf!("Base de Cálculo: {}", formatar_moeda(base_calculo)));
+        }
+        if let Some(aliquota) = valores.aliquota {
+            ui.label(trf!("Alíquota: {}", formatar_aliquota(aliquota)));
+        }
+        if let Some(valor_iss) = valores.valor_iss {
+            ui.label(trf!("Valor do ISS: {}", formatar_moeda(valor_iss)));
+        }
+        if let Some(liquido) = valores.valor_liquido() {
+            ui.label(trf!("Valor Líquido: {}", formatar_moeda(liquido)));
+        }
+        if let (Some(false), Some(declarado)) = (valores.soma_consistente(tolerancia_soma), valores.valor_liquido_nfse) {
+            ui.colored_label(
+                egui::Color32::ORANGE,
+                trf!("⚠ Valor líquido declarado ({}) mais ISS não soma o valor dos serviços", formatar_moeda(declarado)),
+            );
+        }
+        ui.label(tr("Descrição:"));
+        let discriminacao = formatar_discriminacao(&invoice.servico.discriminacao);
+        ui.add(
+            egui::TextEdit::multiline(&mut discriminacao.as_str())
+                .desired_width(f32::INFINITY)
+                .desired_rows(1),
+        );
+        ui.horizontal(|ui| {
+            ui.label(tr("Copiar linha completa (separada por tabulação):"));
+            copy_button(ui, &export::tsv_row(parsed, &export::Coluna::PADRAO));
+        });
+        if !invoice.xml.is_empty() {
+            egui::CollapsingHeader::new(tr("ver XML")).show(ui, |ui| {
+                copy_button(ui, &invoice.xml);
+                egui::ScrollArea::both().max_height(XML_MAX_HEIGHT).show(ui, |ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut invoice.xml.as_str())
+                            .code_editor()
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+            });
+        }
+    });
+    open_error
+}
+
+impl TemplateApp {
+    /// Dispara as ações dos atalhos de teclado, as mesmas dos botões correspondentes.
+    /// Com um campo de texto em foco, as teclas ficam para o campo.
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        // Ctrl+Shift+O vem antes de Ctrl+O, que também aceitaria a combinação com Shift.
+        if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_OPEN_FOLDER)) {
+            self.select_folder();
+        } else if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_OPEN_FILES)) {
+            self.select_files();
+        } else if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_EXPORT_CSV)) {
+            if self.invoices_to_export_count() > 0 {
+                self.export_csv(&export::Coluna::PADRAO);
+            }
+        } else if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_CLEAR_ALL)) {
+            self.clear_all();
+        } else if ctx.input_mut(|i| i.consume_shortcut(&SHORTCUT_RELOAD)) {
+            self.reload();
+        }
+    }
+
+    /// Intercepta o fechamento da janela enquanto há arquivos sendo procurados ou processados e pede confirmação.
+    fn handle_close_request(&mut self, ctx: &egui::Context) {
+        let busy = self.scan.is_some() || self.processing.is_some();
+        if ctx.input(|i| i.viewport().close_requested()) && busy && !self.close_confirmed {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.confirm_close = true;
+        }
+        // Se o processamento terminar enquanto o diálogo está aberto, não há mais o que perder.
+        if !self.confirm_close {
+            return;
+        }
+
+        egui::Window::new(tr("Processamento em andamento"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                if busy {
+                    ui.label(tr("Ainda há arquivos sendo processados. Se sair agora, esse trabalho será perdido."));
+                } else {
+                    ui.label(tr("O processamento terminou."));
+                }
+                ui.label(tr("Deseja sair?"));
+                ui.horizontal(|ui| {
+                    if ui.button(tr("Sair")).clicked() {
+                        self.confirm_close = false;
+                        self.close_confirmed = true;
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                    if ui.button(tr("Cancelar")).clicked() {
+                        self.confirm_close = false;
+                    }
+                });
+            });
+    }
+
+    /// Diálogo de exportação em que o usuário marca as colunas do CSV ou da planilha.
+    /// As colunas saem na ordem em que foram marcadas, e a escolha fica salva nas preferências.
+    fn show_export_columns(&mut self, ctx: &egui::Context) {
+        if !self.show_export_columns {
+            return;
+        }
+        let mut open = true;
+        let mut exportar = None;
+        let has_invoices = self.invoices_to_export_count() > 0;
+        let colunas = &mut self.settings.colunas_exportacao;
+        egui::Window::new(tr("Exportar com colunas escolhidas")).open(&mut open).resizable(false).show(ctx, |ui| {
+            ui.label(tr("Marque os campos a incluir; a ordem das colunas segue a ordem de marcação."));
+            for coluna in export::Coluna::TODAS {
+                let mut marcada = colunas.contains(&coluna);
+                if ui.checkbox(&mut marcada, tr(coluna.titulo())).changed() {
+                    if marcada {
+                        colunas.push(coluna);
+                    } else {
+                        colunas.retain(|c| *c != coluna);
+                    }
+                }
+            }
+            ui.separator();
+            if colunas.is_empty() {
+                ui.weak(tr("Nenhuma coluna marcada."));
+            } else {
+                let ordem: Vec<String> =
+                    colunas.iter().enumerate().map(|(i, coluna)| format!("{}. {}", i + 1, tr(coluna.titulo()))).collect();
+                ui.label(trf!("Ordem: {}", ordem.join("  ·  ")));
+            }
+            ui.horizontal(|ui| {
+                let pronto = has_invoices && !colunas.is_empty();
+                if ui.add_enabled(pronto, egui::Button::new(tr("CSV…"))).clicked() {
+                    exportar = Some(false);
+                }
+                if ui.add_enabled(pronto, egui::Button::new(tr("Planilha XLSX…"))).clicked() {
+                    exportar = Some(true);
+                }
+                if ui.button(tr("Restaurar padrão")).clicked() {
+                    *colunas = export::Coluna::PADRAO.to_vec();
+                }
+            });
+        });
+        self.show_export_columns = open;
+
+        if let Some(xlsx) = exportar {
+            let colunas = self.settings.colunas_exportacao.clone();
+            if xlsx {
+                self.export_xlsx(&colunas);
+            } else {
+                self.export_csv(&colunas);
+            }
+        }
+    }
+
+    /// Janela que compara as notas de duas pastas pela combinação número + CNPJ do prestador
+    /// e lista as que existem em apenas uma delas.
+    fn show_comparacao(&mut self, ctx: &egui::Context) {
+        let Some(comparacao) = &mut self.comparacao else {
+            return;
+        };
+        comparacao.poll();
+        if comparacao.em_andamento() {
+            ctx.request_repaint_after(Duration::from_millis(50));
+        }
+
+        let mut open = true;
+        let mut export = false;
+        let last_dir = self.settings.last_dir.clone();
+        egui::Window::new(tr("Comparar lotes")).open(&mut open).default_width(520.0).show(ctx, |ui| {
+            for (label, pasta) in [
+                (tr("Lote A:"), &mut comparacao.pasta_a),
+                (tr("Lote B:"), &mut comparacao.pasta_b),
+            ] {
+                ui.horizontal(|ui| {
+                    ui.label(label);
+                    if ui.button(tr("Escolher pasta…")).clicked() {
+                        let inicial = pasta.as_ref().or(last_dir.as_ref()).map(|p| p.display().to_string()).unwrap_or_default();
+                        if let Some(folder) = tfd::select_folder_dialog("Selecione uma pasta", &inicial) {
+                            *pasta = Some(PathBuf::from(folder));
+                        }
+                    }
+                    match pasta {
+                        Some(pasta) => ui.label(pasta.display().to_string()),
+                        None => ui.weak(tr("nenhuma pasta escolhida")),
+                    };
+                });
+            }
+
+            ui.horizontal(|ui| {
+                let pronto = comparacao.pasta_a.is_some() && comparacao.pasta_b.is_some() && !comparacao.em_andamento();
+                if ui.add_enabled(pronto, egui::Button::new(tr("Comparar"))).clicked() {
+                    comparacao.iniciar();
+                }
+                if comparacao.em_andamento() {
+                    ui.add(egui::Spinner::new());
+                    ui.label(tr("Lendo as notas dos dois lotes..."));
+                }
+            });
+
+            let Some(diferencas) = &comparacao.diferencas else {
+                return;
+            };
+            ui.separator();
+            if diferencas.is_empty() {
+                ui.colored_label(egui::Color32::DARK_GREEN, tr("Os dois lotes têm as mesmas notas."));
+                return;
+            }
+            let so_a = diferencas.iter().filter(|d| d.lado == comparacao::Lado::A).count();
+            ui.horizontal(|ui| {
+                ui.label(trf!("Só no lote A: {}", so_a));
+                ui.separator();
+                ui.label(trf!("Só no lote B: {}", diferencas.len() - so_a));
+                ui.separator();
+                export = ui.button(tr("Exportar CSV")).clicked();
+            });
+            egui::ScrollArea::vertical().max_height(GROUP_MAX_HEIGHT).show(ui, |ui| {
+                egui::Grid::new("comparacao").striped(true).show(ui, |ui| {
+                    for header in [tr("Situação"), tr("Número"), tr("CNPJ Prestador"), tr("Prestador"), tr("Data"), tr("Valor")] {
+                        ui.strong(header);
+                    }
+                    ui.end_row();
+                    for diferenca in diferencas {
+                        ui.label(match diferenca.lado {
+                            comparacao::Lado::A => tr("Só no lote A"),
+                            comparacao::Lado::B => tr("Só no lote B"),
+                        });
+                        ui.label(diferenca.numero.to_string());
+                        ui.label(formatar_documento(&diferenca.cnpj_prestador));
+                        ui.label(&diferenca.prestador);
+                        ui.label(&diferenca.data_emissao);
+                        ui.label(formatar_moeda(diferenca.valor_servicos));
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+
+        if export {
+            self.export_diferencas_csv();
+        }
+        if !open {
+            self.comparacao = None;
+        }
+    }
+
+    /// Adiciona à seleção os arquivos e pastas arrastados para a janela.
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        // Enquanto o usuário arrasta arquivos sobre a janela, mostra uma camada indicando onde soltar.
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                egui::Id::new("drop_overlay"),
+            ));
+            let screen = ctx.screen_rect();
+            painter.rect_filled(screen, 0.0, egui::Color32::from_black_alpha(180));
+            painter.text(
+                screen.center(),
+                egui::Align2::CENTER_CENTER,
+                tr("Solte os arquivos aqui"),
+                egui::FontId::proportional(28.0),
+                egui::Color32::WHITE,
+            );
+        }
+
+        // Pastas arrastadas são varridas em segundo plano, como em "Selecionar Pasta"; arquivos e pastas
+        // podem vir misturados no mesmo drop.
+        let dropped: Vec<PathBuf> = ctx.input(|i| i.raw.dropped_files.iter().filter_map(|f| f.path.clone()).collect());
+        if dropped.is_empty() {
+            return;
+        }
+        // Uma única pasta solta passa a ser a pasta selecionada, que pode então ser monitorada.
+        if let [folder] = dropped.as_slice() {
+            if folder.is_dir() {
+                if self.watcher.as_ref().is_some_and(|w| w.folder() != folder) {
+                    self.watcher = None;
+                }
+                self.selected_folder = Some(folder.clone());
+                self.settings.last_dir = Some(folder.clone());
+            }
+        }
+        // Somados a um lote existente, os arquivos soltos não mudam o filtro de datas.
+        self.lote_novo |= self.selected_files.is_empty();
+        self.start_scan(dropped, true);
+    }
+
+    /// Procura os arquivos XML e ZIP das pastas (ou arquivos) indicadas numa thread de fundo.
+    /// Ao terminar, os arquivos encontrados substituem a seleção (ou se somam a ela, com `append`) e são processados.
+    fn start_scan(&mut self, roots: Vec<PathBuf>, append: bool) {
+        let (sender, receiver) = mpsc::channel();
+        let found = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&found);
+        let subpastas = self.settings.incluir_subpastas;
+        let somente_arquivos = !roots.iter().any(|root| root.is_dir());
+        thread::spawn(move || {
+            let files: Vec<PathBuf> = roots
+                .iter()
+                .flat_map(|root| walk_input_files(root, subpastas))
+                .inspect(|_| {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                })
+                .collect();
+            let _ = sender.send(files);
+        });
+        self.scan = Some(FolderScan { receiver, found, append, somente_arquivos });
+    }
+
+    /// Recebe o resultado da varredura de pastas e inicia o processamento dos arquivos encontrados.
+    fn poll_scan(&mut self) {
+        let Some(scan) = &self.scan else {
+            return;
+        };
+        let files = match scan.receiver.try_recv() {
+            Ok(files) => files,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => Vec::new(),
+        };
+        let append = scan.append;
+        let somente_arquivos = scan.somente_arquivos;
+        self.scan = None;
+        let nothing_found = files.is_empty();
+        if !append {
+            self.selected_files.clear();
+        }
+        self.add_selected_files(files);
+        // Reprocessa já aqui para que a mensagem não seja apagada pelo processamento do próximo frame.
+        if nothing_found {
+            self.lote_novo = false;
+            if self.selected_files != self.processed_files {
+                self.process_files();
+            }
+            let mensagem = if somente_arquivos {
+                tr("Nenhum dos arquivos soltos é um XML ou ZIP.")
+            } else {
+                tr("Nenhum arquivo XML encontrado nesta pasta.")
+            };
+            self.error_message = Some(mensagem.to_string());
+        }
+    }
+
+    /// Acrescenta arquivos à seleção atual, ignorando os que já fazem parte dela.
+    /// A comparação usa o caminho canônico, de modo que o mesmo arquivo alcançado por caminhos
+    /// diferentes (relativo, com `..` ou por link simbólico) entra uma única vez.
+    fn add_selected_files(&mut self, files: Vec<PathBuf>) {
+        let mut known: HashSet<PathBuf> = self.selected_files.iter().map(|f| canonical_path(f)).collect();
+        for file in files {
+            if known.insert(canonical_path(&file)) {
+                self.selected_files.push(file);
+            }
+        }
+    }
+
+    /// Abre a janela de detalhes da nota, se ainda não estiver aberta.
+    fn open_details(&mut self, index: usize) {
+        if !self.detail_windows.contains(&index) {
+            self.detail_windows.push(index);
+        }
+    }
+
+    /// Mesmo resultado de `visible_indices`, recalculado apenas quando o lote, os filtros ou a ordenação mudam.
+    fn cached_visible_indices(&mut self) -> Vec<usize> {
+        let sort = (self.sort_column, self.sort_ascending);
+        let valid = self.visible_cache.as_ref().is_some_and(|cache| {
+            cache.version == self.invoices_version && cache.sort == sort && cache.filters == self.filters
+        });
+        if !valid {
+            self.visible_cache = Some(VisibleCache {
+                version: self.invoices_version,
+                filters: self.filters.clone(),
+                sort,
+                indices: self.visible_indices(),
+            });
+        }
+        self.visible_cache.as_ref().map(|cache| cache.indices.clone()).unwrap_or_default()
+    }
+
+    /// Retorna os índices das notas que passam pelos filtros ativos, já na ordem escolhida.
+    fn visible_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..self.parsed_invoices.len())
+            .filter(|&i| self.filters.matches(&self.parsed_invoices[i]))
+            .collect();
+
+        if let Some(column) = self.sort_column {
+            // Colunas de texto são comparadas sem acentos e sem diferenciar maiúsculas de minúsculas.
+            // As chaves normalizadas são calculadas uma única vez por nota visível.
+            let mut text_keys = vec![String::new(); self.parsed_invoices.len()];
+            for &i in &indices {
+                let invoice = &self.parsed_invoices[i].inf;
+                let text = match column {
+                    SortColumn::Prestador => invoice.prestador_servico.razao_social.as_str(),
+                    SortColumn::Tomador => invoice.tomador_servico.nome().unwrap_or(""),
+                    SortColumn::Numero | SortColumn::Data | SortColumn::Valor => continue,
+                };
+                text_keys[i] = chave_ordenacao(text);
+            }
+
+            indices.sort_by(|&ia, &ib| {
+                let (a, b) = (&self.parsed_invoices[ia], &self.parsed_invoices[ib]);
+                let ordering = match column {
+                    SortColumn::Numero => a.inf.numero.cmp(&b.inf.numero),
+                    SortColumn::Data => a.data_emissao.cmp(&b.data_emissao),
+                    SortColumn::Prestador | SortColumn::Tomador => text_keys[ia].cmp(&text_keys[ib]),
+                    SortColumn::Valor => a
+                        .inf
+                        .servico
+                        .valores
+                        .valor_servicos
+                        .total_cmp(&b.inf.servico.valores.valor_servicos),
+                };
+                if self.sort_ascending {
+                    ordering
+                } else {
+                    ordering.reverse()
+                }
+            });
+        }
+        indices
+    }
+
+    /// Cabeçalho clicável: o primeiro clique ordena de forma ascendente e os seguintes alternam a direção.
+    fn sort_header(&mut self, ui: &mut egui::Ui, label: &str, column: SortColumn) {
+        let arrow = match (self.sort_column == Some(column), self.sort_ascending) {
+            (true, true) => " ▲",
+            (true, false) => " ▼",
+            (false, _) => "",
+        };
+        let button = egui::Button::new(egui::RichText::new(format!("{}{}", label, arrow)).strong()).frame(false);
+        if ui.add(button).clicked() {
+            if self.sort_column == Some(column) {
+                self.sort_ascending = !self.sort_ascending;
+            } else {
+                self.sort_column = Some(column);
+                self.sort_ascending = true;
+            }
+        }
+    }
+
+    /// Lista as competências do lote com contagem e soma; clicar numa delas alterna o filtro por aquele mês.
+    fn show_competencias(&mut self, ui: &mut egui::Ui) {
+        let grupos = match self.competencias_cache.take() {
+            Some((version, grupos)) if version == self.invoices_version => grupos,
+            _ => {
+                let all: Vec<usize> = (0..self.parsed_invoices.len()).collect();
+                agrupamento::por_competencia(&self.parsed_invoices, &all)
+            }
+        };
+        egui::CollapsingHeader::new(tr("Competências")).show(ui, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for grupo in &grupos {
+                    let selected = self.filters.competencia.as_ref() == Some(&grupo.chave);
+                    let text = trf!(
+                        "{}: {} nota(s), {}",
+                        grupo.nome,
+                        grupo.notas.len(),
+                        formatar_moeda(grupo.total)
+                    );
+                    if ui.selectable_label(selected, text).clicked() {
+                        self.filters.competencia = if selected { None } else { Some(grupo.chave.clone()) };
+                    }
+                }
+                if self.filters.competencia.is_some() && ui.button(tr("Mostrar todas")).clicked() {
+                    self.filters.competencia = None;
+                }
+            });
+        });
+        self.competencias_cache = Some((self.invoices_version, grupos));
+    }
+
+    /// Aviso com os números de nota que faltam na sequência de cada prestador do lote; não aparece sem lacunas.
+    fn show_lacunas(&mut self, ui: &mut egui::Ui) {
+        let lacunas = match self.lacunas_cache.take() {
+            Some((version, lacunas)) if version == self.invoices_version => lacunas,
+            _ => {
+                let all: Vec<usize> = (0..self.parsed_invoices.len()).collect();
+                agrupamento::lacunas_por_prestador(&self.parsed_invoices, &all)
+            }
+        };
+        if !lacunas.is_empty() {
+            let faltantes: u64 = lacunas.iter().map(agrupamento::Lacunas::quantidade).sum();
+            let title = egui::RichText::new(trf!("⚠ Lacunas na numeração: {} nota(s) faltando", faltantes))
+                .color(egui::Color32::ORANGE);
+            egui::CollapsingHeader::new(title).id_salt("lacunas").show(ui, |ui| {
+                egui::ScrollArea::vertical().id_salt("lacunas_rows").max_height(GROUP_MAX_HEIGHT).show(ui, |ui| {
+                    for lacuna in &lacunas {
+                        let faixas: Vec<String> = lacuna
+                            .faixas
+                            .iter()
+                            .map(|&(inicio, fim)| if inicio == fim { inicio.to_string() } else { format!("{}–{}", inicio, fim) })
+                            .collect();
+                        ui.label(format!("{} ({}): {}", lacuna.nome, formatar_documento(&lacuna.cnpj), faixas.join(", ")));
+                    }
+                });
+            });
+        }
+        self.lacunas_cache = Some((self.invoices_version, lacunas));
+    }
+
+    /// Lista suspensa com os prestadores do lote; escolher um mostra apenas as notas dele.
+    fn show_prestador_filter(&mut self, ui: &mut egui::Ui) {
+        let prestadores = match self.prestadores_cache.take() {
+            Some((version, prestadores)) if version == self.invoices_version => prestadores,
+            _ => {
+                let mut prestadores: Vec<(String, String)> = Vec::new();
+                let mut vistos = HashSet::new();
+                for parsed in &self.parsed_invoices {
+                    let prestador = &parsed.inf.prestador_servico;
+                    if vistos.insert(&prestador.identificacao_prestador.cnpj) {
+                        prestadores.push((prestador.identificacao_prestador.cnpj.clone(), prestador.razao_social.clone()));
+                    }
+                }
+                prestadores.sort_by_cached_key(|(_, nome)| chave_ordenacao(nome));
+                prestadores
+            }
+        };
+        let rotulo = |cnpj: &str, nome: &str| format!("{} ({})", nome, formatar_documento(cnpj));
+        ui.horizontal(|ui| {
+            ui.label(tr("Prestador:"));
+            let selecionado = match &self.filters.cnpj_prestador {
+                Some(cnpj) => match prestadores.iter().find(|(c, _)| c == cnpj) {
+                    Some((cnpj, nome)) => rotulo(cnpj, nome),
+                    None => formatar_documento(cnpj),
+                },
+                None => tr("Todos").to_string(),
+            };
+            egui::ComboBox::from_id_salt("filtro_prestador")
+                .selected_text(selecionado)
+                .width(320.0)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.filters.cnpj_prestador, None, tr("Todos"));
+                    for (cnpj, nome) in &prestadores {
+                        ui.selectable_value(&mut self.filters.cnpj_prestador, Some(cnpj.clone()), rotulo(cnpj, nome));
+                    }
+                });
+        });
+        self.prestadores_cache = Some((self.invoices_version, prestadores));
+    }
+
+    /// Conta as notas do lote por tipo de documento do tomador; clicar num tipo alterna o filtro por ele.
+    fn show_tipos_documento(&mut self, ui: &mut egui::Ui) {
+        let mut contagem = [0usize; TipoDocumento::ALL.len()];
+        for parsed in &self.parsed_invoices {
+            let tipo = parsed.inf.tomador_servico.identificacao_tomador.cpf_cnpj.tipo();
+            if let Some(i) = TipoDocumento::ALL.iter().position(|t| *t == tipo) {
+                contagem[i] += 1;
+            }
+        }
+        ui.horizontal(|ui| {
+            ui.label(tr("Tomadores:"));
+            for (tipo, quantidade) in TipoDocumento::ALL.into_iter().zip(contagem) {
+                let selected = self.filters.tipo_documento == Some(tipo);
+                let text = format!("{}: {}", tipo.label(), quantidade);
+                if ui.selectable_label(selected, text).clicked() {
+                    self.filters.tipo_documento = if selected { None } else { Some(tipo) };
+                }
+            }
+        });
+    }
+
+    /// Exibe as notas em grupos colapsáveis com subtotal e contagem; clicar numa nota abre seus detalhes.
+    fn show_groups(&mut self, ui: &mut egui::Ui, grupos: &[agrupamento::Grupo]) {
+        // No agrupamento por prestador, cada grupo mostra sua participação no total das notas exibidas.
+        let total_geral: f64 = grupos.iter().map(|g| g.total).sum();
+        let show_share = self.view_mode == ViewMode::ByPrestador && total_geral > 0.0;
+        egui::ScrollArea::vertical().id_salt("groups").show(ui, |ui| {
+            for grupo in grupos {
+                // A chave só é repetida no título quando difere do nome (por exemplo, o CNPJ do prestador).
+                let nome = if grupo.nome == grupo.chave {
+                    grupo.nome.clone()
+                } else {
+                    format!("{} ({})", grupo.nome, formatar_documento(&grupo.chave))
+                };
+                let mut title = trf!(
+                    "{} — {} nota(s) — {}",
+                    nome,
+                    grupo.notas.len(),
+                    formatar_moeda(grupo.total)
+                );
+                if show_share {
+                    let percentual = grupo.total / total_geral * 100.0;
+                    title.push_str(&format!(" ({}%)", format!("{:.1}", percentual).replace('.', ",")));
+                }
+                egui::CollapsingHeader::new(title)
+                    .id_salt(("group", &grupo.chave))
+                    .show(ui, |ui| {
+                        // Grupos grandes rolam internamente e só desenham as notas visíveis.
+                        let row_height = ui.spacing().interact_size.y;
+                        egui::ScrollArea::vertical()
+                            .id_salt(("group_rows", &grupo.chave))
+                            .max_height(GROUP_MAX_HEIGHT)
+                            .show_rows(ui, row_height, grupo.notas.len(), |ui, range| {
+                                for &index in &grupo.notas[range] {
+                                    let parsed = &self.parsed_invoices[index];
+                                    let invoice = &parsed.inf;
+                                    let text = trf!(
+                                        "Nº {}  ·  {}  ·  {}  ·  {}",
+                                        invoice.numero,
+                                        parsed.data_emissao_formatada(),
+                                        invoice.tomador_servico.nome().unwrap_or(tr("(tomador sem nome)")),
+                                        formatar_moeda(invoice.servico.valores.valor_servicos)
+                                    );
+                                    let text = if invoice.cancelada {
+                                        canceled_text(text)
+                                    } else {
+                                        egui::RichText::new(text)
+                                    };
+                                    if ui.selectable_label(self.detail_windows.contains(&index), text).clicked() {
+                                        self.open_details(index);
+                                    }
+                                }
+                            });
+                    });
+            }
+        });
+    }
+
+    /// Exibe a tabela de notas; clicar numa linha abre seus detalhes numa janela.
+    /// Arrastar o cabeçalho de uma coluna sobre o de outra muda a ordem das colunas.
+    fn show_invoice_table(&mut self, ui: &mut egui::Ui, visible: &[usize]) {
+        let row_height = ui.text_style_height(&egui::TextStyle::Body) + 6.0;
+        let ordem = self.settings.ordem_colunas.clone();
+        let tolerancia_soma = self.settings.tolerancia_soma;
+        let limite_atipico = self.limite_atipico();
+        let mut moved = None;
+        let painter = ui.painter().clone();
+        let drop_stroke = ui.visuals().selection.stroke;
+        let mut table = TableBuilder::new(ui)
+            // As larguras guardadas pela tabela são por posição; com outra ordem, recomeçam do padrão.
+            .id_salt(&ordem)
+            .striped(true)
+            .resizable(true)
+            .sense(egui::Sense::click())
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::auto());
+        for (i, column) in ordem.iter().enumerate() {
+            table = table.column(if i + 1 == ordem.len() { Column::remainder().clip(true) } else { column.largura() });
+        }
+        table
+            .header(22.0, |mut header| {
+                // Marca ou desmarca de uma vez todas as notas exibidas.
+                header.col(|ui| {
+                    let marcadas = visible.iter().filter(|i| self.checked_invoices.contains(i)).count();
+                    let mut todas = !visible.is_empty() && marcadas == visible.len();
+                    let algumas = marcadas > 0 && !todas;
+                    let checkbox = egui::Checkbox::without_text(&mut todas).indeterminate(algumas);
+                    if ui.add(checkbox).on_hover_text(tr("Selecionar todas/nenhuma")).changed() {
+                        if todas {
+                            self.checked_invoices.extend(visible.iter().copied());
+                        } else {
+                            for i in visible {
+                                self.checked_invoices.remove(i);
+                            }
+                        }
+                    }
+                });
+                for &column in &ordem {
+                    let (rect, response) = header.col(|ui| {
+                        ui.dnd_drag_source(egui::Id::new(("table_column", column)), column, |ui| match column.sort() {
+                            Some(sort) => self.sort_header(ui, tr(column.titulo()), sort),
+                            None => {
+                                ui.strong(tr(column.titulo()));
+                            }
+                        });
+                    });
+                    // Marca onde a coluna arrastada vai entrar.
+                    if response.dnd_hover_payload::<TableColumn>().is_some_and(|dragged| *dragged != column) {
+                        painter.vline(rect.left(), rect.y_range(), drop_stroke);
+                    }
+                    if let Some(dragged) = response.dnd_release_payload::<TableColumn>() {
+                        moved = Some((*dragged, column));
+                    }
+                }
+            })
+            .body(|body| {
+                body.rows(row_height, visible.len(), |mut row| {
+                    let index = visible[row.index()];
+                    let parsed = &self.parsed_invoices[index];
+                    row.set_selected(self.detail_windows.contains(&index));
+
+                    row.col(|ui| {
+                        let mut checked = self.checked_invoices.contains(&index);
+                        if ui.add(egui::Checkbox::without_text(&mut checked)).changed() {
+                            if checked {
+                                self.checked_invoices.insert(index);
+                            } else {
+                                self.checked_invoices.remove(&index);
+                            }
+                        }
+                    });
+                    for &column in &ordem {
+                        row.col(|ui| show_table_cell(ui, column, parsed, tolerancia_soma, limite_atipico));
+                    }
+
+                    if row.response().clicked() {
+                        self.open_details(index);
+                    }
+                });
+            });
+
+        if let Some((dragged, target)) = moved {
+            self.move_column(dragged, target);
+        }
+    }
+
+    /// Valor dos serviços acima do qual a nota é destacada como atípica: a média do lote mais o limiar
+    /// configurado em desvios padrão. Canceladas ficam fora do cálculo; `None` com menos de duas notas.
+    fn limite_atipico(&mut self) -> Option<f64> {
+        let estatisticas = match self.estatisticas_cache.take() {
+            Some((version, estatisticas)) if version == self.invoices_version => estatisticas,
+            _ => media_desvio(
+                self.parsed_invoices
+                    .iter()
+                    .filter(|parsed| !parsed.inf.cancelada)
+                    .map(|parsed| parsed.inf.servico.valores.valor_servicos),
+            ),
+        };
+        self.estatisticas_cache = Some((self.invoices_version, estatisticas));
+        estatisticas.map(|(media, desvio)| media + f64::from(self.settings.limiar_atipico) * desvio)
+    }
+
+    /// Põe a coluna arrastada no lugar da coluna onde ela foi solta.
+    fn move_column(&mut self, dragged: TableColumn, target: TableColumn) {
+        let ordem = &mut self.settings.ordem_colunas;
+        let (Some(from), Some(to)) = (ordem.iter().position(|c| *c == dragged), ordem.iter().position(|c| *c == target))
+        else {
+            return;
+        };
+        if from == to {
+            return;
+        }
+        ordem.remove(from);
+        ordem.insert(to, dragged);
+        self.alinhar_colunas_exportacao();
+    }
+
+    /// Reordena as colunas escolhidas para exportação conforme a ordem da tabela.
+    /// As que não aparecem na tabela (ISS, discriminação, anotação) ficam no fim, na ordem em que foram marcadas.
+    fn alinhar_colunas_exportacao(&mut self) {
+        let ordem = &self.settings.ordem_colunas;
+        self.settings.colunas_exportacao.sort_by_key(|coluna| {
+            ordem.iter().position(|c| c.coluna_exportacao() == Some(*coluna)).unwrap_or(usize::MAX)
+        });
+    }
+
+    /// Aplica o tema salvo nas preferências; sem preferência, mantém o tema do sistema.
+    fn apply_theme(&self, ctx: &egui::Context) {
+        match self.settings.dark_mode {
+            Some(true) => ctx.set_theme(egui::Theme::Dark),
+            Some(false) => ctx.set_theme(egui::Theme::Light),
+            None => ctx.set_theme(egui::ThemePreference::System),
+        }
+    }
+
+    /// Caminho inicial dos diálogos: a última pasta usada, seguida do nome de arquivo sugerido.
+    fn dialog_path(&self, file_name: &str) -> String {
+        match &self.settings.last_dir {
+            Some(dir) => dir.join(file_name).display().to_string(),
+            None => file_name.to_string(),
+        }
+    }
+
+    /// Abre o diálogo de seleção de arquivos XML/ZIP e processa os escolhidos.
+    fn select_files(&mut self) {
+        let files = tfd::open_file_dialog_multi(
+            "Selecione os arquivos XML",
+            &self.dialog_path(""),
+            // Os padrões em maiúsculas cobrem os sistemas em que o filtro do diálogo diferencia a capitalização.
+            Some((&["*.xml", "*.XML", "*.zip", "*.ZIP"], "Arquivos XML ou ZIP")),
+        );
+        if let Some(files) = files {
+            let files: Vec<PathBuf> = files.into_iter().map(PathBuf::from).collect();
+            if let Some(dir) = files.first().and_then(|f| f.parent()) {
+                self.settings.last_dir = Some(dir.to_path_buf());
+            }
+            self.lote_novo |= !self.append_selection || self.selected_files.is_empty();
+            if !self.append_selection {
+                self.selected_files.clear();
+                self.selected_folder = None;
+                self.scan = None;
+                self.watcher = None;
+            }
+            self.add_selected_files(files);
+        }
+    }
+
+    /// Abre o diálogo de seleção de pasta e processa os arquivos encontrados nela.
+    fn select_folder(&mut self) {
+        let folder = tfd::select_folder_dialog("Selecione uma pasta", &self.dialog_path(""));
+        if let Some(folder) = folder {
+            let folder = PathBuf::from(folder);
+            self.lote_novo |= !self.append_selection || self.selected_files.is_empty();
+            self.start_scan(vec![folder.clone()], self.append_selection);
+            // O monitoramento de outra pasta não faz sentido para o novo lote.
+            if self.watcher.as_ref().is_some_and(|w| w.folder() != folder) {
+                self.watcher = None;
+            }
+            self.selected_folder = Some(folder.clone());
+            self.settings.last_dir = Some(folder);
+        }
+    }
+
+    /// Pede o destino ao usuário e salva a sessão: arquivos selecionados, filtros, ordenação e anotações do lote.
+    fn save_session(&mut self) {
+        let nome = format!("lote{}", sessao::EXTENSAO);
+        let filtro = format!("*{}", sessao::EXTENSAO);
+        let path = tfd::save_file_dialog_with_filter("Salvar sessão", &self.dialog_path(&nome), &[&filtro], "Sessões");
+        if let Some(path) = path {
+            let path = PathBuf::from(path);
+            let anotacoes = self
+                .parsed_invoices
+                .iter()
+                .filter(|parsed| !parsed.anotacao.is_empty())
+                .map(|parsed| (parsed.identificador.clone(), parsed.anotacao.clone()))
+                .collect();
+            let sessao = sessao::Sessao {
+                arquivos: self.selected_files.clone(),
+                filtros: self.filters.clone(),
+                ordenacao: self.sort_column,
+                crescente: self.sort_ascending,
+                anotacoes,
+            };
+            match sessao::salvar(&path, &sessao) {
+                Ok(()) => self.info_message = Some(trf!("Sessão salva em {}", path.display())),
+                Err(e) => self.error_message = Some(e),
+            }
+        }
+    }
+
+    /// Abre uma sessão salva: restaura filtros, ordenação e anotações e relê os arquivos do disco.
+    /// Os arquivos que não existem mais ficam de fora, com um aviso.
+    fn open_session(&mut self) {
+        let filtro = format!("*{}", sessao::EXTENSAO);
+        let path = tfd::open_file_dialog("Abrir sessão", &self.dialog_path(""), Some((&[&filtro], "Sessões")));
+        let Some(path) = path.map(PathBuf::from) else {
+            return;
+        };
+        let sessao = match sessao::abrir(&path) {
+            Ok(sessao) => sessao,
+            Err(e) => {
+                self.error_message = Some(e);
+                return;
+            }
+        };
+        let (existentes, ausentes): (Vec<PathBuf>, Vec<PathBuf>) =
+            sessao.arquivos.into_iter().partition(|arquivo| arquivo.exists());
+
+        self.selected_files = existentes;
+        self.selected_folder = None;
+        self.scan = None;
+        self.watcher = None;
+        self.filters = sessao.filtros;
+        self.sort_column = sessao.ordenacao;
+        self.sort_ascending = sessao.crescente;
+        self.settings.anotacoes.extend(sessao.anotacoes);
+        self.lote_novo = false;
+        self.process_files();
+
+        if ausentes.is_empty() {
+            self.info_message = Some(trf!("Sessão aberta: {}", path.display()));
+        } else {
+            let lista: Vec<String> = ausentes.iter().map(|arquivo| arquivo.display().to_string()).collect();
+            self.error_message = Some(trf!(
+                "{} arquivo(s) da sessão não existe(m) mais e ficou(aram) de fora: {}",
+                ausentes.len(),
+                lista.join(", ")
+            ));
+        }
+    }
+
+    /// Descarta a seleção de arquivos, as notas processadas e as mensagens exibidas.
+    fn clear_all(&mut self) {
+        self.selected_files.clear();
+        self.selected_folder = None;
+        self.scan = None;
+        self.watcher = None;
+    }
+
+    /// Relê todos os arquivos selecionados do disco, descartando o cache de leitura.
+    fn reload(&mut self) {
+        if self.selected_files.is_empty() {
+            return;
+        }
+        if let Ok(mut cache) = self.parse_cache.lock() {
+            cache.clear();
+        }
+        self.process_files();
+        self.reloading = true;
+    }
+
+    /// Relê do disco um único arquivo do lote, trocando as notas e os erros dele pelos da nova leitura.
+    /// O lote é remontado na ordem da seleção, com os demais arquivos vindos do cache de leitura, para que
+    /// a detecção de duplicatas mantenha, como no processamento, a primeira ocorrência. As janelas de
+    /// detalhes abertas, as marcações e o intervalo do filtro de datas são preservados.
+    fn reload_file(&mut self, path: &Path) {
+        if self.processing.is_some() {
+            return;
+        }
+        if let Ok(mut cache) = self.parse_cache.lock() {
+            cache.remove(path);
+        }
+
+        // Os demais arquivos vêm do cache como foram lidos, mesmo que também tenham mudado no disco:
+        // só o arquivo pedido é relido.
+        let arquivos: Vec<(PathBuf, Vec<DocumentResult>)> = self
+            .processed_files
+            .iter()
+            .map(|file| {
+                let cached = self.parse_cache.lock().ok().and_then(|cache| cache.get(file).map(|c| c.documents.clone()));
+                let documents = cached.unwrap_or_else(|| parse_input_file_cached(file, &self.parse_cache));
+                (file.clone(), documents)
+            })
+            .collect();
+
+        // As notas com janela aberta ou marcadas são reencontradas pela origem, pelo número e pelo prestador.
+        let chave = |parsed: &ParsedInvoice| {
+            let cnpj = parsed.inf.prestador_servico.identificacao_prestador.cnpj.clone();
+            (parsed.source.clone(), parsed.inf.numero, cnpj)
+        };
+        let abertas: Vec<_> = self.detail_windows.iter().map(|&i| chave(&self.parsed_invoices[i])).collect();
+        let marcadas: Vec<_> = self.checked_invoices.iter().map(|&i| chave(&self.parsed_invoices[i])).collect();
+
+        self.parsed_invoices.clear();
+        self.seen_invoices.clear();
+        self.duplicates_ignored = 0;
+        self.file_errors.clear();
+        self.empty_files.clear();
+        self.ignored_files.clear();
+        self.resumo_arquivos.clear();
+        self.desatualizados.remove(path);
+        self.log.escrever(&format!("Arquivo recarregado: {}", path.display()));
+        for (file, documents) in arquivos {
+            self.add_file_documents(file, documents);
+        }
+        self.invoices_version += 1;
+
+        let indices: HashMap<_, usize> =
+            self.parsed_invoices.iter().enumerate().map(|(i, parsed)| (chave(parsed), i)).collect();
+        self.detail_windows = abertas.iter().filter_map(|k| indices.get(k).copied()).collect();
+        self.checked_invoices = marcadas.iter().filter_map(|k| indices.get(k).copied()).collect();
+        self.finish_processing();
+        self.info_message = Some(trf!("Arquivo recarregado: {}", path.display()));
+    }
+
+    /// Verifica periodicamente quais arquivos processados foram modificados no disco desde a leitura,
+    /// comparando a data de modificação atual com a guardada no resumo de cada arquivo.
+    fn poll_desatualizados(&mut self) {
+        if self.processing.is_some() || self.verificacao_desatualizados.elapsed() < INTERVALO_DESATUALIZADOS {
+            return;
+        }
+        self.verificacao_desatualizados = Instant::now();
+        self.desatualizados = self
+            .processed_files
+            .iter()
+            .filter(|path| {
+                let Some(lido) = self.resumo_arquivos.get(*path).and_then(|resumo| resumo.modificado) else {
+                    return false;
+                };
+                fs::metadata(path).and_then(|m| m.modified()).is_ok_and(|atual| atual != lido)
+            })
+            .cloned()
+            .collect();
+    }
+
+    /// Processa a lista de arquivos XML selecionados.
+    fn process_files(&mut self) {
+        self.parsed_invoices.clear();
+        self.invoices_version += 1;
+        self.detail_windows.clear();
+        self.checked_invoices.clear();
+        self.seen_invoices.clear();
+        self.duplicates_ignored = 0;
+        self.last_processing = None;
+        self.file_errors.clear();
+        self.empty_files.clear();
+        self.ignored_files.clear();
+        self.resumo_arquivos.clear();
+        self.desatualizados.clear();
+        self.error_message = None;
+        self.info_message = None;
+        self.reloading = false;
+        self.processed_files = self.selected_files.clone();
+
+        // Só guarda no cache os arquivos que continuam selecionados.
+        if let Ok(mut cache) = self.parse_cache.lock() {
+            let selected: HashSet<&PathBuf> = self.selected_files.iter().collect();
+            cache.retain(|path, _| selected.contains(path));
+        }
+
+        if self.selected_files.is_empty() {
+            self.processing = None;
+            return;
+        }
+
+        self.log.escrever(&format!("Início do processamento: {} arquivo(s)", self.selected_files.len()));
+
+        // Substituir o processamento anterior descarta o receptor, o que encerra a thread antiga.
+        let (sender, receiver) = mpsc::channel();
+        let files = self.selected_files.clone();
+        self.processing = Some(Processing {
+            receiver,
+            files: files.clone(),
+            pending: (0..files.len()).map(|_| None).collect(),
+            next_index: 0,
+            done: 0,
+            started: Instant::now(),
+        });
+
+        // Lê e desserializa os arquivos em paralelo numa thread de fundo, sem bloquear a interface.
+        let cache = Arc::clone(&self.parse_cache);
+        thread::spawn(move || {
+            let _ = files.par_iter().enumerate().try_for_each_with(sender, |sender, (index, path)| {
+                sender.send(FileResult { index, documents: parse_input_file_cached(path, &cache) })
+            });
+        });
+    }
+
+    /// Recebe os resultados da thread de processamento e os incorpora na ordem da seleção.
+    fn poll_processing(&mut self) {
+        let Some(processing) = &mut self.processing else {
+            return;
+        };
+
+        while let Ok(message) = processing.receiver.try_recv() {
+            processing.pending[message.index] = Some(message.documents);
+            processing.done += 1;
+        }
+
+        // Só incorpora um resultado depois que todos os anteriores chegaram, preservando a ordem.
+        let mut ready = Vec::new();
+        while processing.next_index < processing.files.len() {
+            match processing.pending[processing.next_index].take() {
+                Some(documents) => {
+                    ready.push((processing.files[processing.next_index].clone(), documents));
+                    processing.next_index += 1;
+                }
+                None => break,
+            }
+        }
+        let finished = processing.next_index == processing.files.len();
+        let elapsed = (processing.started.elapsed(), processing.files.len());
+
+        for (file, documents) in ready {
+            self.add_file_documents(file, documents);
+        }
+
+        if finished {
+            self.processing = None;
+            self.last_processing = Some(elapsed);
+            self.finish_processing();
+            self.sugerir_periodo();
+            // Com uma varredura em andamento, o lote novo ainda não chegou: este processamento é o anterior.
+            let lote_novo = self.scan.is_none() && std::mem::take(&mut self.lote_novo);
+            if std::mem::take(&mut self.reloading) {
+                self.info_message = Some(trf!("Recarregado: {} arquivo(s) relido(s) do disco", elapsed.1));
+            } else if lote_novo && !self.filters.filter_by_date {
+                self.sugerir_competencia();
+            }
+        }
+    }
+
+    /// Importa os arquivos que chegaram na pasta monitorada, somando-os ao lote atual.
+    fn poll_watcher(&mut self) {
+        // Espera o processamento do lote terminar para não misturar os resultados.
+        if self.processing.is_some() {
+            return;
+        }
+        let Some(watcher) = &mut self.watcher else {
+            return;
+        };
+        let new_files: Vec<PathBuf> = watcher
+            .poll()
+            .into_iter()
+            .filter(|path| !self.selected_files.contains(path))
+            .collect();
+        if new_files.is_empty() {
+            return;
+        }
+
+        let documents: Vec<(PathBuf, Vec<DocumentResult>)> =
+            new_files.par_iter().map(|file| (file.clone(), parse_input_file_cached(file, &self.parse_cache))).collect();
+        self.log.escrever(&format!("Pasta monitorada: {} arquivo(s) novo(s)", new_files.len()));
+        for (file, documents) in documents {
+            self.add_file_documents(file, documents);
+        }
+        self.info_message = Some(trf!("{} arquivo(s) importado(s) da pasta monitorada", new_files.len()));
+        self.selected_files.extend(new_files.iter().cloned());
+        self.processed_files.extend(new_files);
+        self.finish_processing();
+        self.sugerir_periodo();
+    }
+
+    /// Incorpora os documentos lidos de um arquivo selecionado e guarda o resumo da leitura.
+    fn add_file_documents(&mut self, file: PathBuf, documents: Vec<DocumentResult>) {
+        let mut resumo = ResumoArquivo::new(&documents);
+        resumo.modificado = self.parse_cache.lock().ok().and_then(|cache| cache.get(&file).map(|c| c.modified));
+        self.resumo_arquivos.insert(file, resumo);
+        for document in documents {
+            self.add_document(document);
+        }
+    }
+
+    /// Incorpora as notas de um documento lido, ou registra o erro e segue com os demais arquivos do lote.
+    fn add_document(&mut self, document: DocumentResult) {
+        match document.result {
+            Ok(invoices) if invoices.is_empty() => {
+                self.log.escrever(&format!("SEM NOTAS: {}", document.source.display()));
+                self.empty_files.push(document.source);
+            }
+            Ok(invoices) => {
+                self.log.escrever(&format!("OK: {} — {} nota(s)", document.source.display(), invoices.len()));
+                for inf in invoices {
+                    self.add_invoice(ParsedInvoice::new(inf, document.source.clone()));
+                }
+            }
+            Err(DocumentError::NotNfse(e)) => {
+                self.log.escrever(&format!("IGNORADO: {} — {}", document.source.display(), e));
+                self.ignored_files.push(FileError { path: document.source, message: e });
+            }
+            Err(DocumentError::Failed(e)) => {
+                self.log.escrever(&format!("ERRO: {} — {}", document.source.display(), e));
+                self.file_errors.push(FileError { path: document.source, message: e });
+            }
+        }
+    }
+
+    /// Incorpora uma nota ao lote, ignorando repetições quando duplicatas não são permitidas.
+    fn add_invoice(&mut self, mut parsed: ParsedInvoice) {
+        if let Some(anotacao) = self.settings.anotacoes.get(&parsed.identificador) {
+            parsed.anotacao = anotacao.clone();
+        }
+        if !self.allow_duplicates {
+            let key = (parsed.inf.numero, parsed.inf.prestador_servico.identificacao_prestador.cnpj.clone());
+            if !self.seen_invoices.insert(key) {
+                self.duplicates_ignored += 1;
+                return;
+            }
+        }
+        self.parsed_invoices.push(parsed);
+        self.invoices_version += 1;
+    }
+
+    /// Ajustes feitos depois que todos os arquivos do lote foram processados.
+    fn finish_processing(&mut self) {
+        self.log.escrever(&format!(
+            "Fim do processamento: {} nota(s), {} arquivo(s) com erro, {} arquivo(s) ignorado(s), {} duplicada(s) ignorada(s)",
+            self.parsed_invoices.len(),
+            self.file_errors.len(),
+            self.ignored_files.len(),
+            self.duplicates_ignored
+        ));
+        // Diferente da pasta sem XMLs: os arquivos existem, mas nenhum trouxe notas.
+        if self.parsed_invoices.is_empty() && !self.processed_files.is_empty() && self.error_message.is_none() {
+            self.error_message = Some(trf!(
+                "{} arquivo(s) encontrado(s), mas nenhuma nota pôde ser lida. Veja os detalhes abaixo.",
+                self.processed_files.len()
+            ));
+        }
+    }
+
+    /// Sugere o período do lote como intervalo inicial do filtro de datas, se o filtro estiver desligado.
+    fn sugerir_periodo(&mut self) {
+        if !self.filters.filter_by_date {
+            let datas = self.parsed_invoices.iter().filter_map(|p| p.data_emissao);
+            if let (Some(inicio), Some(fim)) = (datas.clone().min(), datas.max()) {
+                self.filters.date_start = inicio;
+                self.filters.date_end = fim;
+            }
+        }
+    }
+
+    /// Num lote com notas de mais de uma competência, filtra as datas pelo mês com mais notas.
+    /// O filtro fica ativo como qualquer outro; desmarcá-lo volta a mostrar o lote inteiro.
+    fn sugerir_competencia(&mut self) {
+        let all: Vec<usize> = (0..self.parsed_invoices.len()).collect();
+        let grupos = agrupamento::por_competencia(&self.parsed_invoices, &all);
+        let com_data: Vec<&agrupamento::Grupo> = grupos.iter().filter(|g| g.chave != agrupamento::SEM_DATA).collect();
+        if com_data.len() < 2 {
+            return;
+        }
+        // Em caso de empate, prevalece a competência mais recente.
+        let Some(predominante) = com_data.iter().max_by_key(|g| (g.notas.len(), &g.chave)) else {
+            return;
+        };
+        let Ok(inicio) = NaiveDate::parse_from_str(&format!("{}-01", predominante.chave), "%Y-%m-%d") else {
+            return;
+        };
+        let Some(fim) = inicio.checked_add_months(Months::new(1)).and_then(|d| d.pred_opt()) else {
+            return;
+        };
+        self.filters.filter_by_date = true;
+        self.filters.date_start = inicio;
+        self.filters.date_end = fim;
+        self.info_message = Some(trf!(
+            "Mostrando a competência predominante ({}, {} de {} notas); limpe o filtro de datas para ver tudo.",
+            predominante.nome,
+            predominante.notas.len(),
+            self.parsed_invoices.len()
+        ));
+    }
+
+    /// Notas a exportar, conforme a opção do rodapé: as visíveis (na ordem exibida) ou o lote inteiro.
+    fn invoices_to_export(&mut self) -> Vec<ParsedInvoice> {
+        if self.export_filtered {
+            let visible = self.cached_visible_indices();
+            visible.into_iter().map(|i| self.parsed_invoices[i].clone()).collect()
+        } else {
+            self.parsed_invoices.clone()
+        }
+    }
+
+    /// Quantidade de notas que `invoices_to_export` devolveria.
+    fn invoices_to_export_count(&mut self) -> usize {
+        if self.export_filtered {
+            self.cached_visible_indices().len()
+        } else {
+            self.parsed_invoices.len()
+        }
+    }
+
+    /// Pede o destino ao usuário e exporta as notas processadas em CSV, com as colunas informadas.
+    fn export_csv(&mut self, colunas: &[export::Coluna]) {
+        let path = tfd::save_file_dialog_with_filter("Salvar CSV", &self.dialog_path("notas.csv"), &["*.csv"], "Arquivos CSV");
+        if let Some(path) = path {
+            let path = PathBuf::from(path);
+            let result = export::export_csv(&path, &self.invoices_to_export(), colunas);
+            self.report_export(&path, result);
+        }
+    }
+
+    /// Pede o destino ao usuário e exporta o resumo dos grupos exibidos (quantidade e total por grupo) em CSV.
+    fn export_groups_csv(&mut self, grupos: &[agrupamento::Grupo]) {
+        let Some(headers) = self.view_mode.group_headers() else {
+            return;
+        };
+        let path = tfd::save_file_dialog_with_filter("Salvar resumo", &self.dialog_path("resumo.csv"), &["*.csv"], "Arquivos CSV");
+        if let Some(path) = path {
+            let path = PathBuf::from(path);
+            let result = export::export_groups_csv(&path, headers, grupos);
+            self.report_export(&path, result);
+        }
+    }
+
+    /// Pede o destino ao usuário e exporta o resumo do agrupamento informado (por exemplo, CNPJ, razão social,
+    /// quantidade de notas e total por prestador), uma linha por grupo, independentemente da visualização atual.
+    fn export_resumo_csv(&mut self, modo: ViewMode, titulo: &str, nome_padrao: &str) {
+        let indices = if self.export_filtered {
+            self.cached_visible_indices()
+        } else {
+            (0..self.parsed_invoices.len()).collect()
+        };
+        let (Some(grupos), Some(headers)) = (modo.agrupar(&self.parsed_invoices, &indices), modo.group_headers()) else {
+            return;
+        };
+        let path =
+            tfd::save_file_dialog_with_filter(titulo, &self.dialog_path(nome_padrao), &["*.csv"], "Arquivos CSV");
+        if let Some(path) = path {
+            let path = PathBuf::from(path);
+            let result = export::export_groups_csv(&path, headers, &grupos);
+            self.report_export(&path, result);
+        }
+    }
+
+    /// Pede o destino ao usuário e exporta as notas processadas em XLSX, com as colunas informadas.
+    fn export_xlsx(&mut self, colunas: &[export::Coluna]) {
+        let path = tfd::save_file_dialog_with_filter("Salvar planilha", &self.dialog_path("notas.xlsx"), &["*.xlsx"], "Planilhas XLSX");
+        if let Some(path) = path {
+            let path = PathBuf::from(path);
+            let result = export::export_xlsx(&path, &self.invoices_to_export(), colunas);
+            self.report_export(&path, result);
+        }
+    }
+
+    /// Pede o destino ao usuário e exporta as notas processadas em JSON.
+    fn export_json(&mut self) {
+        let path = tfd::save_file_dialog_with_filter("Salvar JSON", &self.dialog_path("notas.json"), &["*.json"], "Arquivos JSON");
+        if let Some(path) = path {
+            let path = PathBuf::from(path);
+            let result = export::export_json(&path, &self.invoices_to_export());
+            self.report_export(&path, result);
+        }
+    }
+
+    /// Pede o destino ao usuário e gera o espelho em PDF da nota indicada.
+    fn export_espelho_pdf(&mut self, index: usize) {
+        let nome = format!("espelho_nfse_{}.pdf", self.parsed_invoices[index].inf.numero);
+        let path = tfd::save_file_dialog_with_filter("Salvar espelho", &self.dialog_path(&nome), &["*.pdf"], "Documentos PDF");
+        if let Some(path) = path {
+            let path = PathBuf::from(path);
+            match pdf::export_espelho_pdf(&path, &self.parsed_invoices[index]) {
+                Ok(()) => {
+                    self.error_message = None;
+                    self.info_message = Some(trf!("Espelho da nota salvo em {}", path.display()));
+                }
+                Err(e) => {
+                    self.info_message = None;
+                    self.error_message = Some(e);
+                }
+            }
+        }
+    }
+
+    /// Pede o destino ao usuário e gera o relatório das notas processadas em PDF.
+    fn export_pdf(&mut self) {
+        let path = tfd::save_file_dialog_with_filter("Salvar relatório", &self.dialog_path("relatorio.pdf"), &["*.pdf"], "Documentos PDF");
+        if let Some(path) = path {
+            let path = PathBuf::from(path);
+            let result = pdf::export_pdf_report(&path, &self.invoices_to_export());
+            self.report_export(&path, result);
+        }
+    }
+
+    /// Pede o destino ao usuário e exporta as notas processadas no TXT posicional da contabilidade.
+    fn export_txt_posicional(&mut self) {
+        let path = tfd::save_file_dialog_with_filter("Salvar TXT", &self.dialog_path("notas.txt"), &["*.txt"], "Arquivos de texto");
+        if let Some(path) = path {
+            let path = PathBuf::from(path);
+            let notas: Vec<InfNfse> = self.invoices_to_export().into_iter().map(|p| p.inf).collect();
+            let result = export::export_txt_posicional(&path, &notas);
+            self.report_export(&path, result);
+        }
+    }
+
+    /// Pede o destino ao usuário e exporta em CSV as diferenças da comparação entre lotes.
+    fn export_diferencas_csv(&mut self) {
+        let Some(diferencas) = self.comparacao.as_ref().and_then(|c| c.diferencas.as_ref()) else {
+            return;
+        };
+        let path = tfd::save_file_dialog_with_filter(
+            "Salvar comparação",
+            &self.dialog_path("comparacao.csv"),
+            &["*.csv"],
+            "Arquivos CSV",
+        );
+        if let Some(path) = path {
+            let path = PathBuf::from(path);
+            let result = export::export_diferencas_csv(&path, diferencas);
+            self.report_export(&path, result);
+        }
+    }
+
+    /// Pede o destino ao usuário e exporta em CSV os arquivos com erro, sem notas ou ignorados do último processamento.
+    fn export_erros_csv(&mut self) {
+        let path = tfd::save_file_dialog_with_filter(
+            "Salvar lista de erros",
+            &self.dialog_path("erros.csv"),
+            &["*.csv"],
+            "Arquivos CSV",
+        );
+        let Some(path) = path else {
+            return;
+        };
+        let path = PathBuf::from(path);
+        match export::export_erros_csv(&path, &self.file_errors, &self.empty_files, &self.ignored_files) {
+            Ok(()) => {
+                self.error_message = None;
+                self.info_message = Some(trf!("Lista de erros exportada para {}", path.display()));
+            }
+            Err(e) => {
+                self.info_message = None;
+                self.error_message = Some(e);
+            }
+        }
+    }
+
+    /// Pede uma pasta ao usuário e grava nela um XML por nota, a partir do trecho original de cada uma.
+    /// Se algum dos arquivos já existir, pede confirmação antes de sobrescrever.
+    fn export_xml_individuais(&mut self) {
+        let Some(pasta) = tfd::select_folder_dialog("Selecione a pasta de destino", &self.dialog_path("")) else {
+            return;
+        };
+        let pasta = PathBuf::from(pasta);
+        let invoices = self.invoices_to_export();
+        let arquivos = export::xml_individual_paths(&pasta, &invoices);
+        let existentes = arquivos.iter().filter(|(path, _)| path.exists()).count();
+        if existentes > 0 {
+            let resposta = tfd::message_box_yes_no(
+                tr("Sobrescrever arquivos"),
+                &trf!("{} arquivo(s) já existe(m) na pasta escolhida. Deseja sobrescrevê-lo(s)?", existentes),
+                tfd::MessageBoxIcon::Question,
+                tfd::YesNo::No,
+            );
+            if resposta == tfd::YesNo::No {
+                return;
+            }
+        }
+        let result = export::export_xml_individuais(&arquivos);
+        self.report_export(&pasta, result);
+        if self.error_message.is_none() {
+            let mut mensagem = trf!("{} nota(s) gravada(s) em {}", arquivos.len(), pasta.display());
+            // As notas de arquivos lidos em streaming não guardam o trecho de XML original.
+            let sem_xml = invoices.len() - arquivos.len();
+            if sem_xml > 0 {
+                mensagem.push_str(&trf!("; {} nota(s) sem o XML original ficaram de fora", sem_xml));
+            }
+            self.info_message = Some(mensagem);
+        }
+    }
+
+    /// Exibe o resultado de uma exportação na interface.
+    fn report_export(&mut self, path: &Path, result: Result<(), String>) {
+        match result {
+            Ok(()) => {
+                self.error_message = None;
+                self.info_message = Some(trf!("Notas exportadas para {}", path.display()));
+            }
+            Err(e) => {
+                self.info_message = None;
+                self.error_message = Some(e);
+            }
+        }
+    }
+}
+
+/// Função principal que inicia a aplicação.
+/// Com argumentos na linha de comando, executa a conversão em lote sem abrir a janela.
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return cli::run(&args);
+    }
+
+    // O eframe salva e restaura o tamanho e a posição da janela entre execuções.
+    let options = NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title("Processador de Notas Fiscais")
+            .with_inner_size(DEFAULT_WINDOW_SIZE),
+        persist_window: true,
+        ..Default::default()
+    };
+    // Executa a aplicação nativa com as opções e a estrutura da aplicação.
+    let result = run_native(
+        "Processador de Notas Fiscais",
+        options,
+        Box::new(|cc| Ok(Box::new(TemplateApp::new(cc)))),
+    );
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Erro ao iniciar a interface gráfica: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `CompNfse` mínimo com o número e o valor dos serviços informados; `prefixo` (como `"ns:"`) é aplicado a todas as tags.
+    fn comp_nfse(prefixo: &str, numero: u32, valor_servicos: &str) -> String {
+        format!(
+            "<{p}CompNfse><{p}Nfse><{p}InfNfse><{p}Numero>{numero}</{p}Numero>\
+             <{p}CodigoVerificacao>ABCD</{p}CodigoVerificacao><{p}DataEmissao>2024-01-02T10:00:00</{p}DataEmissao>\
+             <{p}Servico><{p}Valores><{p}ValorServicos>{valor_servicos}</{p}ValorServicos></{p}Valores>\
+             <{p}Discriminacao>Serviço</{p}Discriminacao></{p}Servico>\
+             <{p}PrestadorServico><{p}IdentificacaoPrestador><{p}Cnpj>11222333000181</{p}Cnpj></{p}IdentificacaoPrestador>\
+             <{p}RazaoSocial>Prestador</{p}RazaoSocial></{p}PrestadorServico>\
+             <{p}TomadorServico><{p}IdentificacaoTomador><{p}CpfCnpj><{p}Cnpj>11222333000181</{p}Cnpj></{p}CpfCnpj>\
+             </{p}IdentificacaoTomador><{p}RazaoSocial>Tomador</{p}RazaoSocial></{p}TomadorServico>\
+             </{p}InfNfse></{p}Nfse></{p}CompNfse>",
+            p = prefixo,
+        )
+    }
+
+    /// Resposta de consulta com as notas informadas.
+    fn consultar_nfse_resposta(prefixo: &str, notas: &[String]) -> String {
+        format!(
+            "<{p}ConsultarNfseResposta><{p}ListaNfse>{}</{p}ListaNfse></{p}ConsultarNfseResposta>",
+            notas.concat(),
+            p = prefixo,
+        )
+    }
+
+    /// Desserializa `<Valores>` com o conteúdo informado em `<ValorServicos>`.
+    fn valores(valor_servicos: &str) -> Result<Valores, quick_xml::DeError> {
+        quick_xml::de::from_str(&format!("<Valores><ValorServicos>{}</ValorServicos></Valores>", valor_servicos))
+    }
+
+    #[test]
+    fn valor_com_ponto_ou_virgula_decimal() {
+        for texto in ["1234.56", "1234,56", "1.234,56", " 1234,56 "] {
+            let valores = valores(texto).unwrap();
+            assert_eq!(valores.valor_servicos, 1234.56, "{}", texto);
+            assert!(!valores.valor_servicos_ausente);
+        }
+    }
+
+    #[test]
+    fn valor_entre_aspas() {
+        for texto in ["\"1234.56\"", "&quot;1234.56&quot;", "&quot;1.234,56&quot;", "'1234,56'"] {
+            assert_eq!(valores(texto).unwrap().valor_servicos, 1234.56, "{}", texto);
+        }
+    }
+
+    #[test]
+    fn valor_vazio_marca_ausente() {
+        for texto in ["", "   ", "\"\""] {
+            let valores = valores(texto).unwrap();
+            assert_eq!(valores.valor_servicos, 0.0);
+            assert!(valores.valor_servicos_ausente, "{:?}", texto);
+        }
+    }
+
+    #[test]
+    fn valor_invalido_e_recusado() {
+        let erro = valores("abc").unwrap_err().to_string();
+        assert!(erro.contains("valor numérico inválido"), "{}", erro);
+    }
+
+    /// Mensagem de erro do único documento lido de `xml`.
+    fn erro_documento(xml: &str) -> String {
+        match parse_xml_document(xml.as_bytes(), Path::new("nota.xml")).as_slice() {
+            [DocumentResult { result: Err(DocumentError::Failed(erro)), .. }] => erro.clone(),
+            _ => panic!("esperado um único documento com erro"),
+        }
+    }
+
+    #[test]
+    fn valor_invalido_em_nota_isolada_aponta_o_valor() {
+        let comp_nfse = comp_nfse("", 1, "abc");
+        let nfse = comp_nfse.trim_start_matches("<CompNfse>").trim_end_matches("</CompNfse>");
+        for xml in [comp_nfse.as_str(), nfse, &consultar_nfse_resposta("", std::slice::from_ref(&comp_nfse))] {
+            let erro = erro_documento(xml);
+            assert!(erro.contains("Valor em formato inesperado: \"abc\""), "{}", erro);
+            assert!(!erro.contains("ListaNfse"), "{}", erro);
+        }
+    }
+
+    /// Lê `xml` por `parse_xml_streaming`, a partir de um arquivo temporário com o nome informado.
+    fn ler_em_streaming(nome: &str, xml: &str) -> Option<Vec<DocumentResult>> {
+        let path = std::env::temp_dir().join(format!("{}_{}.xml", nome, std::process::id()));
+        fs::write(&path, xml).unwrap();
+        let documents = parse_xml_streaming(fs::File::open(&path).unwrap(), &path);
+        fs::remove_file(&path).unwrap();
+        documents
+    }
+
+    #[test]
+    fn streaming_recusa_xml_que_nao_e_nfse() {
+        let xml = "<?xml version=\"1.0\"?><nfeProc><NFe><infNFe><ide/></infNFe></NFe></nfeProc>";
+        let documents = ler_em_streaming("nao_nfse", xml).unwrap();
+        let [DocumentResult { result: Err(DocumentError::NotNfse(mensagem)), .. }] = documents.as_slice() else {
+            panic!("esperado um documento recusado por não ser NFS-e");
+        };
+        assert_eq!(mensagem, "Arquivo não é uma NFS-e (elemento raiz <nfeProc>)");
+        let documents = parse_xml_document(xml.as_bytes(), Path::new("nao_nfse.xml"));
+        assert!(matches!(documents.as_slice(), [DocumentResult { result: Err(DocumentError::NotNfse(m)), .. }] if m == mensagem));
+    }
+
+    #[test]
+    fn streaming_nao_guarda_o_trecho_xml() {
+        let notas: Vec<String> = (1..=3).map(|n| comp_nfse("", n, "10.00")).collect();
+        let documents = ler_em_streaming("sem_trecho", &consultar_nfse_resposta("", &notas)).unwrap();
+        let [DocumentResult { result: Ok(invoices), .. }] = documents.as_slice() else {
+            panic!("esperado um único documento lido");
+        };
+        assert_eq!(invoices.len(), 3);
+        assert!(invoices.iter().all(|invoice| invoice.xml.is_empty()));
+    }
+
+    /// Declara o namespace do prefixo (`ns:` vira `xmlns:ns`) no elemento raiz de `xml`.
+    fn declarar_namespace(xml: &str, prefixo: &str) -> String {
+        match prefixo.strip_suffix(':') {
+            Some(nome) => xml.replacen('>', &format!(" xmlns:{}=\"http://www.abrasf.org.br/nfse.xsd\">", nome), 1),
+            None => xml.to_string(),
+        }
+    }
+
+    /// Números e valores das notas lidas, falhando se algum documento tiver erro.
+    fn notas_lidas(documents: &[DocumentResult]) -> Vec<(u32, f32)> {
+        documents
+            .iter()
+            .flat_map(|document| match &document.result {
+                Ok(invoices) => invoices.iter().map(|i| (i.numero, i.servico.valores.valor_servicos)).collect::<Vec<_>>(),
+                Err(e) => panic!("erro ao ler {}: {:?}", document.source.display(), e),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn xml_com_e_sem_prefixo_de_namespace() {
+        for prefixo in ["", "ns:", "nfse:"] {
+            let comp_nfse_isolado = declarar_namespace(&comp_nfse(prefixo, 7, "70.00"), prefixo);
+            let notas = [comp_nfse(prefixo, 1, "10.00"), comp_nfse(prefixo, 2, "20,00")];
+            let lista = declarar_namespace(&consultar_nfse_resposta(prefixo, &notas), prefixo);
+            for (xml, esperado) in [(comp_nfse_isolado, vec![(7, 70.0)]), (lista, vec![(1, 10.0), (2, 20.0)])] {
+                let documents = parse_xml_document(xml.as_bytes(), Path::new("nota.xml"));
+                assert_eq!(notas_lidas(&documents), esperado, "documento, prefixo {:?}", prefixo);
+                let documents = ler_em_streaming("prefixo", &xml).unwrap();
+                assert_eq!(notas_lidas(&documents), esperado, "streaming, prefixo {:?}", prefixo);
+            }
+        }
+    }
+
+    #[test]
+    fn recarregar_o_primeiro_arquivo_mantem_a_primeira_ocorrencia_da_duplicata() {
+        let pasta = std::env::temp_dir().join(format!("nfse_recarregar_{}", std::process::id()));
+        fs::create_dir_all(&pasta).unwrap();
+        let gravar = |nome: &str, notas: &[(u32, &str)]| {
+            let notas: Vec<String> = notas.iter().map(|&(numero, valor)| comp_nfse("", numero, valor)).collect();
+            let path = pasta.join(nome);
+            fs::write(&path, consultar_nfse_resposta("", &notas)).unwrap();
+            path
+        };
+        let primeiro = gravar("a.xml", &[(1, "10.00"), (2, "20.00")]);
+        let segundo = gravar("b.xml", &[(1, "99.00"), (3, "30.00")]);
+
+        let mut app = TemplateApp { log: registro::Registro::desativado(), ..Default::default() };
+        app.selected_files = vec![primeiro.clone(), segundo.clone()];
+        app.process_files();
+        while app.processing.is_some() {
+            std::thread::sleep(Duration::from_millis(5));
+            app.poll_processing();
+        }
+        let lote = |app: &TemplateApp| -> Vec<(u32, PathBuf)> {
+            app.parsed_invoices.iter().map(|p| (p.inf.numero, p.source.clone())).collect()
+        };
+        assert_eq!(lote(&app), vec![(1, primeiro.clone()), (2, primeiro.clone()), (3, segundo.clone())]);
+        assert_eq!(app.duplicates_ignored, 1);
+
+        // Com a janela da nota 3 aberta, o primeiro arquivo ganha uma nota e é recarregado.
+        app.detail_windows = vec![2];
+        gravar("a.xml", &[(1, "10.00"), (2, "20.00"), (4, "40.00")]);
+        app.reload_file(&primeiro);
+        fs::remove_dir_all(&pasta).unwrap();
+
+        assert_eq!(
+            lote(&app),
+            vec![(1, primeiro.clone()), (2, primeiro.clone()), (4, primeiro.clone()), (3, segundo.clone())]
+        );
+        assert_eq!(app.parsed_invoices[0].inf.servico.valores.valor_servicos, 10.0);
+        assert_eq!(app.duplicates_ignored, 1);
+        assert_eq!(app.detail_windows, vec![3]);
+    }
+    /// Mede a leitura de um lote de 2000 arquivos, um por vez e em paralelo com rayon.
+    /// Não roda por padrão: `cargo test --release -- --ignored --nocapture lote_2000_arquivos`.
+    #[test]
+    #[ignore]
+    fn lote_2000_arquivos() {
+        let pasta = std::env::temp_dir().join(format!("nfse_lote_2000_{}", std::process::id()));
+        fs::create_dir_all(&pasta).unwrap();
+        let arquivos: Vec<PathBuf> = (0..2000)
+            .map(|i| {
+                let notas: Vec<String> = (0..20).map(|n| comp_nfse("", i * 20 + n, "1234.56")).collect();
+                let path = pasta.join(format!("{:04}.xml", i));
+                fs::write(&path, consultar_nfse_resposta("", &notas)).unwrap();
+                path
+            })
+            .collect();
+
+        let contar = |resultados: Vec<Vec<DocumentResult>>| -> usize {
+            resultados.iter().flatten().filter_map(|d| d.result.as_ref().ok()).map(Vec::len).sum()
+        };
+        let inicio = Instant::now();
+        let sequencial = contar(arquivos.iter().map(parse_input_file).collect());
+        let tempo_sequencial = inicio.elapsed();
+        let inicio = Instant::now();
+        let paralelo = contar(arquivos.par_iter().map(parse_input_file).collect());
+        let tempo_paralelo = inicio.elapsed();
+        fs::remove_dir_all(&pasta).unwrap();
+
+        println!(
+            "{} threads: sequencial {:?}, paralelo {:?} ({:.1}x)",
+            rayon::current_num_threads(),
+            tempo_sequencial,
+            tempo_paralelo,
+            tempo_sequencial.as_secs_f64() / tempo_paralelo.as_secs_f64()
+        );
+        assert_eq!(sequencial, 40_000);
+        assert_eq!(paralelo, sequencial);
+    }
 }
@@ -0,0 +1,89 @@
+// Monitoramento de uma pasta para importar automaticamente os XMLs que chegam nela.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{has_xml_extension, has_zip_extension};
+
+/// Tempo sem novas alterações até o arquivo ser considerado completo.
+/// Evita ler um XML que ainda está sendo copiado para a pasta.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// Observa uma pasta (e subpastas) e acumula os arquivos XML/ZIP criados ou alterados nela.
+pub struct FolderWatcher {
+    folder: PathBuf,
+    /// Mantido vivo enquanto durar o monitoramento; descartá-lo encerra a observação.
+    _watcher: RecommendedWatcher,
+    receiver: mpsc::Receiver<PathBuf>,
+    /// Arquivos alterados recentemente, com o instante da última alteração.
+    pending: HashMap<PathBuf, Instant>,
+    /// Com o monitoramento pausado, os arquivos novos ficam aguardando até a retomada.
+    pub paused: bool,
+}
+
+impl FolderWatcher {
+    /// Começa a observar a pasta informada e, com `subpastas`, também as subpastas.
+    pub fn start(folder: &Path, subpastas: bool) -> Result<Self, String> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            // Erros do sistema de arquivos não interrompem o monitoramento.
+            let Ok(event) = event else {
+                return;
+            };
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                for path in event.paths {
+                    if has_xml_extension(&path) || has_zip_extension(&path) {
+                        let _ = sender.send(path);
+                    }
+                }
+            }
+        })
+        .map_err(|e| format!("Erro ao iniciar o monitoramento: {}", e))?;
+        let mode = if subpastas { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        watcher
+            .watch(folder, mode)
+            .map_err(|e| format!("Erro ao monitorar a pasta \"{}\": {}", folder.display(), e))?;
+
+        Ok(Self {
+            folder: folder.to_path_buf(),
+            _watcher: watcher,
+            receiver,
+            pending: HashMap::new(),
+            paused: false,
+        })
+    }
+
+    /// Pasta observada.
+    pub fn folder(&self) -> &Path {
+        &self.folder
+    }
+
+    /// Retorna os arquivos que pararam de ser alterados e estão prontos para importação.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let now = Instant::now();
+        while let Ok(path) = self.receiver.try_recv() {
+            self.pending.insert(path, now);
+        }
+        if self.paused {
+            return Vec::new();
+        }
+
+        let mut ready: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(path, changed_at)| now - **changed_at >= SETTLE_TIME && path.is_file())
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &ready {
+            self.pending.remove(path);
+        }
+        // Arquivos removidos antes de serem lidos não serão mais importados.
+        self.pending.retain(|path, _| path.exists());
+        ready.sort();
+        ready
+    }
+}
@@ -0,0 +1,324 @@
+// Geração de relatórios em PDF a partir das notas processadas.
+
+use std::fs;
+use std::io::{BufWriter, Cursor};
+use std::path::Path;
+
+use chrono::Local;
+use printpdf::{IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Point};
+
+use crate::documento::formatar_documento;
+use crate::formato::{formatar_aliquota, formatar_discriminacao, formatar_moeda, somar_valores};
+use crate::{Endereco, ParsedInvoice};
+
+/// Dimensões de uma página A4 em milímetros.
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 15.0;
+
+/// Altura de cada linha da tabela e tamanho da fonte usada nela.
+const ROW_HEIGHT: f32 = 6.0;
+const FONT_SIZE: f32 = 9.0;
+
+/// Posição das colunas da tabela do relatório.
+const COL_NUMERO: f32 = MARGIN;
+const COL_DATA: f32 = MARGIN + 22.0;
+const COL_TOMADOR: f32 = MARGIN + 46.0;
+/// A coluna de valor é alinhada à direita, terminando na margem.
+const COL_VALOR_END: f32 = PAGE_WIDTH - MARGIN;
+/// Quantidade máxima de caracteres exibidos do nome do tomador.
+const TOMADOR_MAX_CHARS: usize = 60;
+
+/// Quantidade máxima de caracteres por linha no espelho da nota; textos maiores são quebrados.
+const ESPELHO_MAX_CHARS: usize = 100;
+
+/// Primeira linha da tabela, logo abaixo do cabeçalho da página.
+const TABLE_TOP: f32 = PAGE_HEIGHT - MARGIN - 24.0;
+
+/// Converte pontos tipográficos em milímetros.
+fn pt_to_mm(pt: f32) -> f32 {
+    pt * 25.4 / 72.0
+}
+
+/// Fontes embutidas no documento: uma proporcional para textos e uma monoespaçada para valores.
+struct Fonts {
+    regular: IndirectFontRef,
+    mono: IndirectFontRef,
+}
+
+impl Fonts {
+    /// Embute no documento as fontes que acompanham o egui, que cobrem os acentos do português.
+    fn load(doc: &PdfDocumentReference) -> Result<Self, String> {
+        let load = |bytes: &'static [u8]| {
+            doc.add_external_font(Cursor::new(bytes))
+                .map_err(|e| format!("Erro ao carregar a fonte do PDF: {}", e))
+        };
+        Ok(Self {
+            regular: load(epaint_default_fonts::UBUNTU_LIGHT)?,
+            mono: load(epaint_default_fonts::HACK_REGULAR)?,
+        })
+    }
+}
+
+/// Escreve um texto monoespaçado alinhado à direita na posição `x_end`.
+fn text_right(layer: &PdfLayerReference, text: &str, size: f32, x_end: f32, y: f32, font: &IndirectFontRef) {
+    // Na fonte Hack cada caractere ocupa 0,6 em.
+    let width = pt_to_mm(size * 0.6) * text.chars().count() as f32;
+    layer.use_text(text, size, Mm(x_end - width), Mm(y), font);
+}
+
+/// Desenha uma linha horizontal de uma margem à outra.
+fn horizontal_rule(layer: &PdfLayerReference, y: f32) {
+    layer.add_line(Line {
+        points: vec![
+            (Point::new(Mm(MARGIN), Mm(y)), false),
+            (Point::new(Mm(PAGE_WIDTH - MARGIN), Mm(y)), false),
+        ],
+        is_closed: false,
+    });
+}
+
+/// Encurta o texto para caber na coluna, indicando o corte com reticências.
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        let cut: String = text.chars().take(max_chars - 1).collect();
+        format!("{}…", cut)
+    }
+}
+
+/// Cabeçalho de cada página: título, data de geração, número da página e títulos das colunas.
+fn page_header(layer: &PdfLayerReference, fonts: &Fonts, generated_at: &str, page: usize, pages: usize) {
+    let top = PAGE_HEIGHT - MARGIN;
+    layer.use_text("Relatório de Notas Fiscais", 14.0, Mm(MARGIN), Mm(top - 5.0), &fonts.regular);
+    layer.use_text(format!("Gerado em {}", generated_at), 8.0, Mm(MARGIN), Mm(top - 11.0), &fonts.regular);
+    let page_label = format!("Página {} de {}", page, pages);
+    text_right(layer, &page_label, 8.0, COL_VALOR_END, top - 11.0, &fonts.mono);
+    horizontal_rule(layer, top - 14.0);
+
+    let y = top - 19.0;
+    layer.use_text("Número", FONT_SIZE, Mm(COL_NUMERO), Mm(y), &fonts.regular);
+    layer.use_text("Data", FONT_SIZE, Mm(COL_DATA), Mm(y), &fonts.regular);
+    layer.use_text("Tomador", FONT_SIZE, Mm(COL_TOMADOR), Mm(y), &fonts.regular);
+    text_right(layer, "Valor", FONT_SIZE, COL_VALOR_END, y, &fonts.mono);
+    horizontal_rule(layer, y - 2.0);
+}
+
+/// Exporta um relatório em PDF com a tabela das notas e o total ao final, quebrando em várias páginas.
+pub fn export_pdf_report(path: &Path, invoices: &[ParsedInvoice]) -> Result<(), String> {
+    let rows_per_page = ((TABLE_TOP - MARGIN) / ROW_HEIGHT).floor() as usize;
+    // O total ocupa duas linhas ao final da tabela (separador e valor).
+    let pages = (invoices.len() + 2).div_ceil(rows_per_page).max(1);
+    let generated_at = Local::now().format("%d/%m/%Y %H:%M").to_string();
+
+    let (doc, first_page, first_layer) =
+        PdfDocument::new("Relatório de Notas Fiscais", Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Página 1");
+    let fonts = Fonts::load(&doc)?;
+
+    let mut layer = doc.get_page(first_page).get_layer(first_layer);
+    page_header(&layer, &fonts, &generated_at, 1, pages);
+    let mut page = 1;
+    let mut row = 0;
+
+    for parsed in invoices {
+        if row == rows_per_page {
+            let (new_page, new_layer) =
+                doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), format!("Página {}", page + 1));
+            layer = doc.get_page(new_page).get_layer(new_layer);
+            page += 1;
+            row = 0;
+            page_header(&layer, &fonts, &generated_at, page, pages);
+        }
+
+        let invoice = &parsed.inf;
+        let y = TABLE_TOP - ROW_HEIGHT * row as f32;
+        layer.use_text(invoice.numero.to_string(), FONT_SIZE, Mm(COL_NUMERO), Mm(y), &fonts.regular);
+        layer.use_text(parsed.data_emissao_formatada(), FONT_SIZE, Mm(COL_DATA), Mm(y), &fonts.regular);
+        layer.use_text(
+            truncate(invoice.tomador_servico.nome().unwrap_or(""), TOMADOR_MAX_CHARS),
+            FONT_SIZE,
+            Mm(COL_TOMADOR),
+            Mm(y),
+            &fonts.regular,
+        );
+        let valor = formatar_moeda(invoice.servico.valores.valor_servicos);
+        text_right(&layer, &valor, FONT_SIZE, COL_VALOR_END, y, &fonts.mono);
+        row += 1;
+    }
+
+    // Total ao final, numa página nova se não houver espaço para as duas linhas.
+    if row + 2 > rows_per_page {
+        let (new_page, new_layer) = doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), format!("Página {}", page + 1));
+        layer = doc.get_page(new_page).get_layer(new_layer);
+        page += 1;
+        row = 0;
+        page_header(&layer, &fonts, &generated_at, page, pages);
+    }
+    let y = TABLE_TOP - ROW_HEIGHT * row as f32;
+    horizontal_rule(&layer, y + ROW_HEIGHT - 2.0);
+    let total = somar_valores(invoices.iter().map(|p| p.inf.servico.valores.valor_servicos));
+    layer.use_text(format!("Total ({} notas)", invoices.len()), FONT_SIZE, Mm(COL_NUMERO), Mm(y - 1.0), &fonts.regular);
+    text_right(&layer, &formatar_moeda(total), FONT_SIZE, COL_VALOR_END, y - 1.0, &fonts.mono);
+
+    let file = fs::File::create(path)
+        .map_err(|e| format!("Erro ao salvar o arquivo \"{}\": {}", path.display(), e))?;
+    doc.save(&mut BufWriter::new(file))
+        .map_err(|e| format!("Erro ao gerar o PDF \"{}\": {}", path.display(), e))
+}
+
+/// Quebra o texto em linhas de até `max_chars` caracteres, sem partir palavras que caibam numa linha.
+/// As quebras de linha do próprio texto são mantidas.
+fn quebrar_linhas(texto: &str, max_chars: usize) -> Vec<String> {
+    let mut linhas = Vec::new();
+    for paragrafo in texto.lines() {
+        let mut linha = String::new();
+        for palavra in paragrafo.split_whitespace() {
+            let mut palavra: String = palavra.to_string();
+            // Palavras maiores que a linha (URLs, códigos) são partidas.
+            while palavra.chars().count() > max_chars {
+                if !linha.is_empty() {
+                    linhas.push(std::mem::take(&mut linha));
+                }
+                let resto = palavra.chars().skip(max_chars).collect();
+                linhas.push(palavra.chars().take(max_chars).collect());
+                palavra = resto;
+            }
+            if !linha.is_empty() && linha.chars().count() + 1 + palavra.chars().count() > max_chars {
+                linhas.push(std::mem::take(&mut linha));
+            }
+            if !linha.is_empty() {
+                linha.push(' ');
+            }
+            linha.push_str(&palavra);
+        }
+        linhas.push(linha);
+    }
+    linhas
+}
+
+/// Posição de escrita no espelho, que desce a cada linha escrita.
+struct Espelho<'a> {
+    layer: PdfLayerReference,
+    fonts: &'a Fonts,
+    y: f32,
+}
+
+impl Espelho<'_> {
+    /// Título de uma seção, seguido de uma linha horizontal.
+    fn secao(&mut self, titulo: &str) {
+        self.y -= 3.0;
+        self.layer.use_text(titulo, 11.0, Mm(MARGIN), Mm(self.y), &self.fonts.regular);
+        horizontal_rule(&self.layer, self.y - 1.5);
+        self.y -= ROW_HEIGHT + 1.0;
+    }
+
+    /// Um campo no formato "Rótulo: valor"; valores longos continuam nas linhas seguintes.
+    fn campo(&mut self, rotulo: &str, valor: &str) {
+        for linha in quebrar_linhas(&format!("{}: {}", rotulo, valor), ESPELHO_MAX_CHARS) {
+            self.linha(&linha);
+        }
+    }
+
+    /// Uma linha de texto; abaixo da margem inferior, nada mais é escrito.
+    fn linha(&mut self, texto: &str) {
+        if self.y < MARGIN {
+            return;
+        }
+        self.layer.use_text(texto, FONT_SIZE, Mm(MARGIN), Mm(self.y), &self.fonts.regular);
+        self.y -= ROW_HEIGHT - 1.0;
+    }
+
+    /// As linhas do endereço, quando informado.
+    fn endereco(&mut self, endereco: &Option<Endereco>) {
+        for linha in endereco.as_ref().map(Endereco::linhas).unwrap_or_default() {
+            self.linha(&linha);
+        }
+    }
+}
+
+/// Exporta o espelho de uma nota: um PDF de uma página com os dados da nota, do prestador, do tomador,
+/// do serviço e dos valores, além da discriminação. Discriminações longas demais são cortadas no fim da página.
+pub fn export_espelho_pdf(path: &Path, parsed: &ParsedInvoice) -> Result<(), String> {
+    let invoice = &parsed.inf;
+    let titulo = format!("Espelho da NFS-e nº {}", invoice.numero);
+    let (doc, page, layer) = PdfDocument::new(&titulo, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Página 1");
+    let fonts = Fonts::load(&doc)?;
+    let layer = doc.get_page(page).get_layer(layer);
+
+    let top = PAGE_HEIGHT - MARGIN;
+    layer.use_text(&titulo, 14.0, Mm(MARGIN), Mm(top - 5.0), &fonts.regular);
+    let generated_at = Local::now().format("%d/%m/%Y %H:%M").to_string();
+    layer.use_text(format!("Gerado em {}", generated_at), 8.0, Mm(MARGIN), Mm(top - 11.0), &fonts.regular);
+    if invoice.cancelada {
+        text_right(&layer, "NOTA CANCELADA", 11.0, COL_VALOR_END, top - 5.0, &fonts.mono);
+    }
+    let mut espelho = Espelho { layer, fonts: &fonts, y: top - 14.0 };
+
+    espelho.secao("Nota");
+    espelho.campo("Número", &invoice.numero.to_string());
+    espelho.campo("Data de emissão", &parsed.data_emissao_formatada());
+    espelho.campo("Competência", &parsed.competencia());
+    if let Some(codigo) = &invoice.codigo_verificacao {
+        espelho.campo("Código de verificação", codigo);
+    }
+
+    let prestador = &invoice.prestador_servico;
+    espelho.secao("Prestador");
+    espelho.campo("Razão social", &prestador.razao_social);
+    espelho.campo("CNPJ", &formatar_documento(&prestador.identificacao_prestador.cnpj));
+    espelho.endereco(&prestador.endereco);
+
+    let tomador = &invoice.tomador_servico;
+    espelho.secao("Tomador");
+    espelho.campo("Razão social", tomador.nome().unwrap_or("(tomador sem nome)"));
+    let identificacao = &tomador.identificacao_tomador;
+    if identificacao.no_exterior() {
+        espelho.campo("Documento no exterior (NIF)", identificacao.documento().unwrap_or(""));
+    } else if let Some(documento) = identificacao.documento() {
+        espelho.campo("CPF/CNPJ", &formatar_documento(documento));
+    }
+    espelho.endereco(&tomador.endereco);
+
+    let servico = &invoice.servico;
+    espelho.secao("Serviço");
+    if let Some(item) = &servico.item_lista_servico {
+        espelho.campo("Item da lista de serviços", item);
+    }
+    if let Some(codigo) = &servico.codigo_tributacao_municipio {
+        espelho.campo("Código de tributação municipal", codigo);
+    }
+
+    let valores = &servico.valores;
+    espelho.secao("Valores");
+    espelho.campo("Valor dos serviços", &formatar_moeda(valores.valor_servicos));
+    if let Some(base_calculo) = valores.base_calculo {
+        espelho.campo("Base de cálculo", &formatar_moeda(base_calculo));
+    }
+    if let Some(aliquota) = valores.aliquota {
+        espelho.campo("Alíquota", &formatar_aliquota(aliquota));
+    }
+    if let Some(valor_iss) = valores.valor_iss {
+        espelho.campo("Valor do ISS", &formatar_moeda(valor_iss));
+    }
+    if let Some(liquido) = valores.valor_liquido() {
+        espelho.campo("Valor líquido", &formatar_moeda(liquido));
+    }
+
+    espelho.secao("Discriminação");
+    let linhas = quebrar_linhas(&formatar_discriminacao(&servico.discriminacao), ESPELHO_MAX_CHARS);
+    let total = linhas.len();
+    for (i, linha) in linhas.iter().enumerate() {
+        // Na última linha que cabe, avisa que o texto continua no XML.
+        if espelho.y - (ROW_HEIGHT - 1.0) < MARGIN && i + 1 < total {
+            espelho.linha("(texto cortado; veja a discriminação completa no XML)");
+            break;
+        }
+        espelho.linha(linha);
+    }
+
+    let file = fs::File::create(path)
+        .map_err(|e| format!("Erro ao salvar o arquivo \"{}\": {}", path.display(), e))?;
+    doc.save(&mut BufWriter::new(file))
+        .map_err(|e| format!("Erro ao gerar o PDF \"{}\": {}", path.display(), e))
+}
@@ -0,0 +1,59 @@
+// Registro do processamento em arquivo, para conferir depois o que aconteceu em cada lote.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+
+/// Nome do arquivo de log dentro da pasta de dados do programa.
+const LOG_FILE_NAME: &str = "processamento.log";
+
+/// Tamanho a partir do qual o log é arquivado (renomeado para `.log.1`) e recomeçado.
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Arquivo de log aberto para acréscimo. Se não puder ser aberto, as mensagens são descartadas.
+pub struct Registro {
+    caminho: Option<PathBuf>,
+    arquivo: Option<File>,
+}
+
+impl Registro {
+    /// Abre o log na pasta de dados do usuário (por exemplo, `~/.local/share/vizualizador-nfse`).
+    pub fn abrir() -> Self {
+        let caminho = dirs::data_dir().map(|dir| dir.join("vizualizador-nfse").join(LOG_FILE_NAME));
+        let arquivo = caminho.as_deref().and_then(|caminho| abrir_arquivo(caminho).ok());
+        Self { caminho, arquivo }
+    }
+
+    /// Registro que descarta as mensagens, para os testes.
+    #[cfg(test)]
+    pub fn desativado() -> Self {
+        Self { caminho: None, arquivo: None }
+    }
+
+    /// Caminho do arquivo de log, se ele pôde ser aberto.
+    pub fn caminho(&self) -> Option<&Path> {
+        self.arquivo.as_ref().and(self.caminho.as_deref())
+    }
+
+    /// Acrescenta uma linha ao log, precedida da data e hora.
+    pub fn escrever(&mut self, mensagem: &str) {
+        if let Some(arquivo) = &mut self.arquivo {
+            let linha = format!("[{}] {}\n", Local::now().format("%Y-%m-%d %H:%M:%S"), mensagem);
+            // Falhas ao gravar o log não devem interromper o processamento.
+            let _ = arquivo.write_all(linha.as_bytes());
+        }
+    }
+}
+
+/// Cria a pasta do log, arquiva o arquivo anterior se estiver grande demais e o abre para acréscimo.
+fn abrir_arquivo(caminho: &Path) -> std::io::Result<File> {
+    if let Some(pasta) = caminho.parent() {
+        fs::create_dir_all(pasta)?;
+    }
+    if fs::metadata(caminho).is_ok_and(|m| m.len() > MAX_LOG_SIZE) {
+        fs::rename(caminho, caminho.with_extension("log.1"))?;
+    }
+    OpenOptions::new().create(true).append(true).open(caminho)
+}
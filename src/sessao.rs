@@ -0,0 +1,37 @@
+// Sessão de trabalho: o lote, os filtros, a ordenação e as anotações, salvos num arquivo `.sessao.json`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{InvoiceFilters, SortColumn};
+
+/// Extensão sugerida para os arquivos de sessão.
+pub const EXTENSAO: &str = ".sessao.json";
+
+/// Estado do trabalho que pode ser salvo e reaberto depois.
+#[derive(Serialize, Deserialize)]
+pub struct Sessao {
+    /// Arquivos selecionados, relidos do disco ao abrir a sessão.
+    pub arquivos: Vec<PathBuf>,
+    pub filtros: InvoiceFilters,
+    pub ordenacao: Option<SortColumn>,
+    pub crescente: bool,
+    /// Anotações das notas do lote, pelo identificador da nota.
+    pub anotacoes: HashMap<String, String>,
+}
+
+/// Grava a sessão no arquivo informado.
+pub fn salvar(path: &Path, sessao: &Sessao) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(sessao)
+        .map_err(|e| format!("Erro ao gerar a sessão \"{}\": {}", path.display(), e))?;
+    fs::write(path, json).map_err(|e| format!("Erro ao salvar o arquivo \"{}\": {}", path.display(), e))
+}
+
+/// Lê uma sessão salva por `salvar`.
+pub fn abrir(path: &Path) -> Result<Sessao, String> {
+    let json = fs::read_to_string(path).map_err(|e| format!("Erro ao abrir o arquivo \"{}\": {}", path.display(), e))?;
+    serde_json::from_str(&json).map_err(|e| format!("O arquivo \"{}\" não é uma sessão válida: {}", path.display(), e))
+}